@@ -0,0 +1,540 @@
+// Copyright (c) 2022 DanWillans
+use crate::config::xdg_directory;
+use crate::play::PlayingScene;
+use crate::scene::{Context, Scene, Transition};
+use crate::scores::format_duration;
+use crate::view::draw_menu;
+use crate::{BoardView, Command, DrawColor, GameBoard, InputEvent, Level, Menu, MenuInput};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+// The campaign that comes with the game, each a built in level and the food that opens its exit
+const BUILT_IN_CAMPAIGN: [(&str, u16); 3] = [("box", 8), ("pillars", 10), ("cross", 12)];
+
+// The layout of a campaign file. Levels are played in the order they're listed:
+//
+// [[level]]
+// map = "box"    # a built in level or a level file, relative to the campaign file
+// food = 8       # food to eat before the exit opens
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CampaignFile {
+    #[serde(rename = "level")]
+    levels: Vec<CampaignLevelFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CampaignLevelFile {
+    map: String,
+    food: u16,
+}
+
+// The campaign that comes with the game in the records. Campaign files are recorded under their
+// full path so this can't be mistaken for one.
+const BUILT_IN_KEY: &str = "built in";
+
+// The best a level of a campaign has been finished in
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LevelRecord {
+    // The built in campaign or the path to a campaign file
+    pub campaign: String,
+    // Where the level is in the campaign, starting from 0. The same map can be in a campaign more
+    // than once with different amounts of food.
+    pub level: usize,
+    // Seconds of play, not counting pauses
+    pub time: f64,
+    pub score: u32,
+}
+
+// The best time and score for every campaign level that's been finished
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CampaignRecords {
+    #[serde(default, rename = "level")]
+    records: Vec<LevelRecord>,
+}
+
+impl CampaignRecords {
+    // $XDG_DATA_HOME/rusty_snake/campaign.toml, falling back to ~/.local/share when it isn't set
+    pub fn default_path() -> Option<PathBuf> {
+        Some(xdg_directory("XDG_DATA_HOME", ".local/share")?.join("campaign.toml"))
+    }
+
+    // Read the records from `path`. A missing file means nothing has been finished yet.
+    pub fn load(path: &Path) -> Result<CampaignRecords, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(CampaignRecords::default()),
+            Err(err) => return Err(format!("Couldn't read {}: {}", path.display(), err)),
+        };
+        toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    // The file is written alongside the old one and renamed over it so it's never left half written
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let error = |err: std::io::Error| format!("Couldn't save {}: {}", path.display(), err);
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(error)?;
+        }
+        let text = toml::to_string(self).map_err(|err| err.to_string())?;
+        let temporary = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&temporary, text).map_err(error)?;
+        fs::rename(&temporary, path).map_err(error)
+    }
+
+    pub fn get(&self, campaign: &str, level: usize) -> Option<&LevelRecord> {
+        self.records
+            .iter()
+            .find(|record| record.campaign == campaign && record.level == level)
+    }
+
+    // Keep the best time and score for a level that was just finished. Returns whether the time
+    // and the score were new bests.
+    pub fn record(&mut self, campaign: &str, level: usize, time: f64, score: u32) -> (bool, bool) {
+        let Some(record) = self
+            .records
+            .iter_mut()
+            .find(|record| record.campaign == campaign && record.level == level)
+        else {
+            self.records.push(LevelRecord {
+                campaign: campaign.to_string(),
+                level,
+                time,
+                score,
+            });
+            return (true, true);
+        };
+        let best_time = time < record.time;
+        let best_score = score > record.score;
+        record.time = record.time.min(time);
+        record.score = record.score.max(score);
+        (best_time, best_score)
+    }
+}
+
+// A list of levels played one after the other. Eating enough food on a level opens its exit and
+// going through it finishes the level and unlocks the next one.
+pub struct Campaign {
+    // What the campaign's records are kept under
+    key: String,
+    levels: Vec<Level>,
+    // The level being played
+    current: usize,
+    records_path: Option<PathBuf>,
+    records: CampaignRecords,
+    // Why the records couldn't be loaded or saved
+    error: Option<String>,
+}
+
+impl Campaign {
+    fn new(key: &str, levels: Vec<Level>) -> Self {
+        let records_path = CampaignRecords::default_path();
+        let (records, error) = match records_path.as_deref().map(CampaignRecords::load) {
+            Some(Ok(records)) => (records, None),
+            Some(Err(err)) => (CampaignRecords::default(), Some(err)),
+            None => (CampaignRecords::default(), None),
+        };
+        Campaign {
+            key: key.to_string(),
+            levels,
+            current: 0,
+            records_path,
+            records,
+            error,
+        }
+    }
+
+    // The campaign that comes with the game
+    pub fn built_in() -> Self {
+        let levels = BUILT_IN_CAMPAIGN
+            .into_iter()
+            .filter_map(|(name, food)| Some(Level::by_name(name)?.with_target(food)))
+            .collect();
+        Campaign::new(BUILT_IN_KEY, levels)
+    }
+
+    // Load a campaign file. Its records are kept under the file's full path.
+    pub fn load(path: &Path) -> Result<Campaign, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        let file: CampaignFile =
+            toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        if file.levels.is_empty() {
+            return Err(format!("{}: there aren't any levels", path.display()));
+        }
+        let directory = path.parent().unwrap_or_else(|| Path::new("."));
+        let levels = file
+            .levels
+            .into_iter()
+            .map(|level| {
+                let map = match Level::by_name(&level.map) {
+                    Some(map) => map,
+                    None => Level::load(&directory.join(&level.map))?,
+                };
+                Ok(map.with_target(level.food))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        Ok(Campaign::new(&key.to_string_lossy(), levels))
+    }
+
+    pub fn levels(&self) -> &[Level] {
+        &self.levels
+    }
+
+    pub fn record(&self, index: usize) -> Option<&LevelRecord> {
+        self.levels.get(index)?;
+        self.records.get(&self.key, index)
+    }
+
+    // The first level is always open and every other one opens once the one before is finished
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index == 0 || self.record(index - 1).is_some()
+    }
+
+    // Keep the result of finishing the current level. Returns whether the time and score were new
+    // bests.
+    fn finish_level(&mut self, time: f64, score: u32) -> (bool, bool) {
+        if self.current >= self.levels.len() {
+            return (false, false);
+        }
+        let bests = self.records.record(&self.key, self.current, time, score);
+        if let Some(path) = &self.records_path {
+            self.error = self.records.save(path).err();
+        }
+        bests
+    }
+}
+
+// Start the campaign level at `index`, which replaces the level in the config so restarting plays
+// it again. None if there's no such level or it doesn't fit on the screen.
+fn play_level(context: &mut Context, index: usize) -> Option<PlayingScene> {
+    let campaign = context.campaign.as_mut()?;
+    let level = campaign.levels.get(index)?.clone();
+    campaign.current = index;
+    context.config.level = Some(level);
+    PlayingScene::new(context)
+}
+
+// The label for a level in the campaign menu, with its best time and score once it's finished
+fn level_label(campaign: &Campaign, index: usize) -> String {
+    let level = &campaign.levels[index];
+    let name = format!("{}. {:<10}", index + 1, level.name());
+    if !campaign.is_unlocked(index) {
+        return format!("{} locked", name);
+    }
+    match campaign.record(index) {
+        Some(record) => format!(
+            "{} best {:>5} {:>5}",
+            name,
+            format_duration(record.time.round() as u32),
+            record.score
+        ),
+        None => format!("{} {} food", name, level.target().unwrap_or(0)),
+    }
+}
+
+// The campaign's menu. Lists the levels with their best times and scores and plays the one that's
+// chosen if it's been unlocked.
+pub struct CampaignScene {
+    // Levels by index, and None to quit
+    menu: Menu<Option<usize>>,
+    // Why the level that was chosen can't be played yet
+    message: Option<String>,
+}
+
+impl CampaignScene {
+    pub fn new() -> Self {
+        CampaignScene {
+            menu: Menu::new("CAMPAIGN", vec![("Quit".to_string(), None)]),
+            message: None,
+        }
+    }
+}
+
+impl Default for CampaignScene {
+    fn default() -> Self {
+        CampaignScene::new()
+    }
+}
+
+impl Scene for CampaignScene {
+    // Build the menu from the campaign, starting on the first level that hasn't been finished
+    fn enter(&mut self, context: &mut Context) {
+        let Some(campaign) = &context.campaign else {
+            return;
+        };
+        let mut items: Vec<(String, Option<usize>)> = (0..campaign.levels.len())
+            .map(|index| (level_label(campaign, index), Some(index)))
+            .collect();
+        items.push(("Quit".to_string(), None));
+        self.menu = Menu::new("CAMPAIGN", items);
+        let next = (0..campaign.levels.len())
+            .find(|index| campaign.record(*index).is_none())
+            .unwrap_or(0);
+        self.menu.select(Some(next));
+    }
+
+    // Back from a level, which may have changed the records
+    fn resume(&mut self, context: &mut Context) {
+        self.enter(context);
+    }
+
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let InputEvent::Key(key) = input else {
+            return Transition::Stay;
+        };
+        let command = context.config.keys.command_for_key(key);
+        if command == Some(Command::Quit) {
+            return Transition::Quit;
+        }
+        self.message = None;
+        match self.menu.handle_key(key, command) {
+            MenuInput::Chosen(Some(index)) => {
+                let unlocked = context
+                    .campaign
+                    .as_ref()
+                    .is_some_and(|campaign| campaign.is_unlocked(index));
+                if !unlocked {
+                    self.message = Some(format!("Finish level {} to unlock this one", index));
+                    return Transition::Stay;
+                }
+                match play_level(context, index) {
+                    Some(playing) => Transition::Push(Box::new(playing)),
+                    None => Transition::Stay,
+                }
+            }
+            MenuInput::Chosen(None) | MenuInput::Back => Transition::Quit,
+            MenuInput::Adjusted(..) | MenuInput::Moved | MenuInput::Ignored => Transition::Stay,
+        }
+    }
+
+    fn draw(&self, context: &mut Context) {
+        draw_menu(&self.menu, &mut context.screen);
+        let screen = &mut context.screen;
+        let bottom = screen.height().saturating_sub(2);
+        if let Some(message) = self.message.clone() {
+            screen.update_with_string(2, bottom.saturating_sub(1), message, DrawColor::White);
+        }
+        if let Some(err) = context.campaign.as_ref().and_then(|c| c.error.clone()) {
+            screen.update_with_string(2, bottom, err, DrawColor::Red);
+        }
+    }
+}
+
+// Shown over a level that was just finished, with how long it took and whether that was a new
+// best. Enter plays the next level and Esc goes back to the campaign menu.
+pub struct LevelCompleteScene {
+    // The game as it ended
+    board: GameBoard,
+    points: u32,
+    // Seconds of play
+    time: f64,
+    best_time: bool,
+    best_score: bool,
+}
+
+impl LevelCompleteScene {
+    pub fn new(board: GameBoard, points: u32, time: f64) -> Self {
+        LevelCompleteScene {
+            board,
+            points,
+            time,
+            best_time: false,
+            best_score: false,
+        }
+    }
+
+    fn next_level(context: &Context) -> Option<usize> {
+        let campaign = context.campaign.as_ref()?;
+        Some(campaign.current + 1).filter(|next| *next < campaign.levels.len())
+    }
+}
+
+impl Scene for LevelCompleteScene {
+    fn enter(&mut self, context: &mut Context) {
+        if let Some(campaign) = &mut context.campaign {
+            (self.best_time, self.best_score) = campaign.finish_level(self.time, self.points);
+        }
+    }
+
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let InputEvent::Key(key) = input else {
+            return Transition::Stay;
+        };
+        match context.config.keys.command_for_key(key) {
+            Some(Command::Start | Command::Restart) => {
+                match LevelCompleteScene::next_level(context) {
+                    Some(next) => match play_level(context, next) {
+                        Some(playing) => Transition::Reset(Box::new(playing)),
+                        None => Transition::Stay,
+                    },
+                    None => Transition::Home,
+                }
+            }
+            Some(Command::Pause) => Transition::Home,
+            Some(Command::Quit) => Transition::Quit,
+            _ => Transition::Stay,
+        }
+    }
+
+    fn draw(&self, context: &mut Context) {
+        let Some(board_view) = BoardView::fit_board(
+            self.board.columns(),
+            self.board.rows(),
+            context.config.theme,
+            &context.screen,
+        ) else {
+            return;
+        };
+        let last = LevelCompleteScene::next_level(context).is_none();
+        let screen = &mut context.screen;
+        let title = if last {
+            "You finished the campaign, well done!"
+        } else {
+            "Level complete!"
+        };
+        board_view.draw_centred_text(0, title.to_string(), DrawColor::Green, screen);
+        let result = format!(
+            "Time {}  Score {}",
+            format_duration(self.time.round() as u32),
+            self.points
+        );
+        board_view.draw_centred_text(1, result, DrawColor::White, screen);
+        let bests = match (self.best_time, self.best_score) {
+            (true, true) => "New best time and score!",
+            (true, false) => "New best time!",
+            (false, true) => "New best score!",
+            (false, false) => "",
+        };
+        board_view.draw_centred_text(2, bests.to_string(), DrawColor::Green, screen);
+        let keys = if last {
+            "Press Enter to go back to the campaign."
+        } else {
+            "Enter plays the next level and Esc goes back to the campaign."
+        };
+        board_view.draw_centred_text(3, keys.to_string(), DrawColor::White, screen);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A campaign that doesn't read or write the records file
+    fn campaign(key: &str, levels: Vec<Level>) -> Campaign {
+        Campaign {
+            key: key.to_string(),
+            levels,
+            current: 0,
+            records_path: None,
+            records: CampaignRecords::default(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn keeps_the_best_time_and_score_separately() {
+        let mut records = CampaignRecords::default();
+        assert_eq!(records.record("a", 0, 30.0, 50), (true, true));
+        assert_eq!(records.record("a", 0, 40.0, 60), (false, true));
+        assert_eq!(records.record("a", 0, 20.0, 10), (true, false));
+        let record = records.get("a", 0).unwrap();
+        assert_eq!((record.time, record.score), (20.0, 60));
+        assert!(records.get("a", 1).is_none());
+        assert!(records.get("b", 0).is_none());
+    }
+
+    #[test]
+    fn levels_unlock_one_at_a_time() {
+        let box_level = Level::by_name("box").unwrap();
+        let mut campaign = campaign("test", vec![box_level.clone(); 3]);
+        assert!(campaign.is_unlocked(0));
+        assert!(!campaign.is_unlocked(1));
+        campaign.finish_level(10.0, 5);
+        assert!(campaign.is_unlocked(1));
+        assert!(!campaign.is_unlocked(2));
+    }
+
+    #[test]
+    fn the_same_map_twice_has_its_own_records() {
+        let box_level = Level::by_name("box").unwrap();
+        let mut campaign = campaign(
+            "test",
+            vec![
+                box_level.clone().with_target(3),
+                box_level.clone().with_target(20),
+                box_level.with_target(5),
+            ],
+        );
+        campaign.current = 1;
+        campaign.finish_level(10.0, 5);
+        assert!(campaign.record(0).is_none());
+        assert!(campaign.record(1).is_some());
+        assert!(!campaign.is_unlocked(1));
+        assert!(campaign.is_unlocked(2));
+    }
+
+    #[test]
+    fn campaigns_keep_their_own_records() {
+        let mut records = CampaignRecords::default();
+        records.record(BUILT_IN_KEY, 0, 10.0, 5);
+        let mut built_in = campaign(BUILT_IN_KEY, Vec::new());
+        built_in.records = records.clone();
+        built_in.levels = vec![Level::by_name("box").unwrap()];
+        let mut other = campaign("/home/me/campaign.toml", built_in.levels.clone());
+        other.records = records;
+        assert!(built_in.record(0).is_some());
+        assert!(other.record(0).is_none());
+    }
+
+    #[test]
+    fn records_round_trip_through_the_file() {
+        let path =
+            std::env::temp_dir().join(format!("rusty_snake_campaign_{}.toml", std::process::id()));
+        assert_eq!(
+            CampaignRecords::load(&path).unwrap(),
+            CampaignRecords::default()
+        );
+        let mut records = CampaignRecords::default();
+        records.record(BUILT_IN_KEY, 2, 12.5, 40);
+        records.record("/a/b.toml", 0, 3.0, 1);
+        records.save(&path).unwrap();
+        assert_eq!(CampaignRecords::load(&path).unwrap(), records);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn loads_a_campaign_file() {
+        let directory =
+            std::env::temp_dir().join(format!("rusty_snake_campaign_file_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("mine.txt"), "..>.E\n.....\n").unwrap();
+        let path = directory.join("campaign.toml");
+        fs::write(
+            &path,
+            "[[level]]\nmap = \"mine.txt\"\nfood = 2\n\n[[level]]\nmap = \"box\"\nfood = 8\n",
+        )
+        .unwrap();
+        let campaign = Campaign::load(&path).unwrap();
+        assert_ne!(campaign.key, BUILT_IN_KEY);
+        let levels = campaign.levels();
+        assert_eq!(levels.len(), 2);
+        assert_eq!((levels[0].name(), levels[0].target()), ("mine", Some(2)));
+        assert_eq!((levels[1].name(), levels[1].target()), ("box", Some(8)));
+
+        fs::write(&path, "").unwrap();
+        assert!(Campaign::load(&path).is_err());
+        fs::write(&path, "[[level]]\nmap = \"missing.txt\"\nfood = 2\n").unwrap();
+        assert!(Campaign::load(&path).is_err());
+        fs::write(&path, "[[level]]\nmap = \"box\"\nfood = 2\nspeed = 3\n").unwrap();
+        assert!(Campaign::load(&path).is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright (c) 2022 DanWillans
+use crate::input::{parse_key, KeyBindings};
+use crate::{Command, Direction, GlyphTheme, Level, Palette, SpeedCurve};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Everything about the game that can be changed without rebuilding it. The defaults are how the
+// game has always played. Settings come from config.toml and can be overridden on the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    // How many times a second the snake moves at the start of a game
+    pub speed: f64,
+    // How the speed goes up as the snake eats
+    pub speed_curve: SpeedCurve,
+    // Columns and rows of the board. None fills the terminal.
+    pub board_size: Option<(u16, u16)>,
+    // Play on a level with walls. Its size is used instead of `board_size`.
+    pub level: Option<Level>,
+    // The snake goes through the edges of the board and comes back in at the opposite side
+    pub wrap: bool,
+    // Use the same seed for every game so they can be reproduced
+    pub seed: Option<u64>,
+    pub theme: GlyphTheme,
+    pub palette: Palette,
+    pub keys: KeyBindings,
+    // Draw the frame rate in the top right corner
+    pub show_fps: bool,
+    // How many times a second the screen is drawn. The snake moves at `speed` whatever this is.
+    pub frame_rate: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            speed: 10.0,
+            speed_curve: SpeedCurve::default(),
+            board_size: None,
+            level: None,
+            wrap: false,
+            seed: None,
+            theme: GlyphTheme::default(),
+            palette: Palette::default(),
+            keys: KeyBindings::default(),
+            show_fps: true,
+            frame_rate: 60.0,
+        }
+    }
+}
+
+// The layout of config.toml. Everything is optional and keeps its default when left out.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    speed: Option<f64>,
+    speed_curve: Option<Vec<f64>>,
+    foods_per_level: Option<u16>,
+    // Columns and rows of the board
+    width: Option<u16>,
+    height: Option<u16>,
+    // A built in level or a level file, relative to the config file
+    level: Option<String>,
+    wrap: Option<bool>,
+    #[serde(deserialize_with = "toml_seed::deserialize_option")]
+    seed: Option<u64>,
+    theme: Option<String>,
+    palette: Option<String>,
+    show_fps: Option<bool>,
+    frame_rate: Option<f64>,
+    keys: KeysFile,
+}
+
+// The [keys] table. Each entry lists every key for that command and replaces the default keys.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct KeysFile {
+    north: Option<Vec<String>>,
+    east: Option<Vec<String>>,
+    south: Option<Vec<String>>,
+    west: Option<Vec<String>>,
+    start: Option<Vec<String>>,
+    restart: Option<Vec<String>>,
+    pause: Option<Vec<String>>,
+    quit: Option<Vec<String>>,
+}
+
+// The rusty_snake directory inside an XDG base directory. `variable` is the environment variable
+// that sets the base directory and `fallback` is where it is relative to the home directory when
+// the variable isn't set.
+pub(crate) fn xdg_directory(variable: &str, fallback: &str) -> Option<PathBuf> {
+    let base = env::var_os(variable)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(fallback)))?;
+    Some(base.join("rusty_snake"))
+}
+
+// Seeds in TOML files. TOML integers are i64s so a seed too big for one is stored as the negative
+// number with the same bits, and read back the same way.
+pub(crate) mod toml_seed {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(*seed as i64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        Ok(i64::deserialize(deserializer)? as u64)
+    }
+
+    pub fn deserialize_option<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        Ok(Option::<i64>::deserialize(deserializer)?.map(|seed| seed as u64))
+    }
+}
+
+impl Config {
+    // $XDG_CONFIG_HOME/rusty_snake/config.toml, falling back to ~/.config when it isn't set
+    pub fn default_path() -> Option<PathBuf> {
+        Some(xdg_directory("XDG_CONFIG_HOME", ".config")?.join("config.toml"))
+    }
+
+    // Load the config file from the default path. It's fine for it not to exist.
+    pub fn load_default() -> Result<Config, String> {
+        match Config::default_path() {
+            Some(path) if path.exists() => Config::load(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        // Palette files are found relative to the config file
+        let directory = path.parent().unwrap_or_else(|| Path::new("."));
+        Config::parse(&text, directory).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    // Read the settings in `text`. Relative palette paths are looked up in `directory`.
+    pub fn parse(text: &str, directory: &Path) -> Result<Config, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|err| err.to_string())?;
+        let mut config = Config::default();
+        if let Some(speed) = file.speed {
+            config.set_speed(speed)?;
+        }
+        if file.speed_curve.is_some() || file.foods_per_level.is_some() {
+            config.speed_curve = SpeedCurve::new(
+                file.speed_curve
+                    .unwrap_or_else(|| config.speed_curve.multipliers().to_vec()),
+                file.foods_per_level
+                    .unwrap_or(config.speed_curve.foods_per_level()),
+            )?;
+        }
+        match (file.width, file.height) {
+            (Some(width), Some(height)) => config.set_board_size(width, height)?,
+            (None, None) => {}
+            _ => return Err("width and height have to be set together".to_string()),
+        }
+        if let Some(level) = file.level {
+            config.level = Some(match Level::by_name(&level) {
+                Some(level) => level,
+                None => Level::load(&directory.join(level))?,
+            });
+        }
+        config.wrap = file.wrap.unwrap_or(config.wrap);
+        config.seed = file.seed;
+        if let Some(theme) = file.theme {
+            config.set_theme(&theme)?;
+        }
+        if let Some(palette) = file.palette {
+            config.palette = match Palette::by_name(&palette) {
+                Some(palette) => palette,
+                None => Palette::load(&directory.join(palette))?,
+            };
+        }
+        config.show_fps = file.show_fps.unwrap_or(config.show_fps);
+        if let Some(frame_rate) = file.frame_rate {
+            config.set_frame_rate(frame_rate)?;
+        }
+
+        let keys = [
+            (file.keys.north, Command::Turn(Direction::North)),
+            (file.keys.east, Command::Turn(Direction::East)),
+            (file.keys.south, Command::Turn(Direction::South)),
+            (file.keys.west, Command::Turn(Direction::West)),
+            (file.keys.start, Command::Start),
+            (file.keys.restart, Command::Restart),
+            (file.keys.pause, Command::Pause),
+            (file.keys.quit, Command::Quit),
+        ];
+        for (names, command) in keys {
+            if let Some(names) = names {
+                config.keys.unbind(command);
+                for name in names {
+                    let key = parse_key(&name).ok_or(format!("'{}' isn't a key", name))?;
+                    config.keys.bind(key, command);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn set_speed(&mut self, speed: f64) -> Result<(), String> {
+        if !speed.is_finite() || speed <= 0.0 {
+            return Err(format!("speed has to be more than 0, not {}", speed));
+        }
+        self.speed = speed;
+        Ok(())
+    }
+
+    pub fn set_frame_rate(&mut self, frame_rate: f64) -> Result<(), String> {
+        if !frame_rate.is_finite() || frame_rate <= 0.0 {
+            return Err(format!(
+                "frame_rate has to be more than 0, not {}",
+                frame_rate
+            ));
+        }
+        self.frame_rate = frame_rate;
+        Ok(())
+    }
+
+    pub fn set_board_size(&mut self, columns: u16, rows: u16) -> Result<(), String> {
+        Level::check_size(columns, rows)?;
+        self.board_size = Some((columns, rows));
+        Ok(())
+    }
+
+    pub fn set_theme(&mut self, name: &str) -> Result<(), String> {
+        self.theme = GlyphTheme::by_name(name).ok_or(format!("there's no '{}' theme", name))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyCode;
+
+    fn parse(text: &str) -> Result<Config, String> {
+        Config::parse(text, Path::new("."))
+    }
+
+    #[test]
+    fn empty_file_is_the_defaults() {
+        assert_eq!(parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn keys_replace_the_defaults_for_their_command() {
+        let config = parse("[keys]\nnorth = [\"k\", \"Up\"]\nquit = [\"Esc\"]\n").unwrap();
+        let keys = |command| config.keys.keys_for(command).collect::<Vec<_>>();
+        assert_eq!(
+            keys(Command::Turn(Direction::North)),
+            [KeyCode::Char('k'), KeyCode::Up]
+        );
+        assert_eq!(keys(Command::Quit), [KeyCode::Esc]);
+        assert_eq!(
+            keys(Command::Turn(Direction::South)),
+            KeyBindings::default()
+                .keys_for(Command::Turn(Direction::South))
+                .collect::<Vec<_>>()
+        );
+        assert!(parse("[keys]\nnorth = [\"NotAKey\"]\n").is_err());
+    }
+
+    #[test]
+    fn width_and_height_are_set_together() {
+        assert_eq!(
+            parse("width = 30\nheight = 12\n").unwrap().board_size,
+            Some((30, 12))
+        );
+        assert!(parse("width = 30\n").is_err());
+        assert!(parse("height = 12\n").is_err());
+        assert!(parse("width = 2\nheight = 12\n").is_err());
+    }
+
+    #[test]
+    fn big_seeds_are_read_like_replay_files_store_them() {
+        assert_eq!(parse("seed = 42\n").unwrap().seed, Some(42));
+        assert_eq!(parse("seed = -2\n").unwrap().seed, Some(u64::MAX - 1));
+    }
+
+    #[test]
+    fn level_and_palette_files_are_relative_to_the_config_file() {
+        let directory =
+            std::env::temp_dir().join(format!("rusty_snake_config_{}", std::process::id()));
+        fs::create_dir_all(directory.join("extras")).unwrap();
+        fs::write(directory.join("extras/mine.txt"), "..>.\n....\n").unwrap();
+        fs::write(directory.join("extras/mine.palette"), "red = \"#ff0000\"\n").unwrap();
+        let path = directory.join("config.toml");
+        fs::write(
+            &path,
+            "level = \"extras/mine.txt\"\npalette = \"extras/mine.palette\"\n",
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.level.unwrap().name(), "mine");
+        assert_eq!(config.palette.red, crate::PaletteColor::Rgb(255, 0, 0));
+
+        // Built in names win over files
+        let config = Config::parse("level = \"box\"\npalette = \"light\"\n", &directory).unwrap();
+        assert_eq!(config.level, Level::by_name("box"));
+        assert_eq!(config.palette, Palette::LIGHT);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        assert!(parse("sped = 3\n").is_err());
+        assert!(parse("[keys]\nup = [\"k\"]\n").is_err());
+        assert!(parse("speed = \"fast\"\n").is_err());
+    }
+}
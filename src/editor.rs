@@ -0,0 +1,208 @@
+// Copyright (c) 2022 DanWillans
+use crate::play::PlayingScene;
+use crate::scene::{Context, Scene, Transition};
+use crate::view::draw_screen_too_small;
+use crate::{BoardView, Command, Direction, DrawColor, InputEvent, Level};
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::path::PathBuf;
+
+// The next way round for the start of the snake, turning clockwise
+fn clockwise(direction: Direction) -> Direction {
+    match direction {
+        Direction::North => Direction::East,
+        Direction::East => Direction::South,
+        Direction::South => Direction::West,
+        Direction::West => Direction::North,
+    }
+}
+
+// Edits a level file. The arrow keys move the cursor, Space puts up or takes down a wall, E moves
+// the exit to the cursor and Enter moves the start of the snake to the cursor, turning it if it's
+// already there. Ctrl+S saves and Ctrl+T plays the level as it is, coming back here when the game
+// is over.
+pub struct EditorScene {
+    level: Level,
+    path: PathBuf,
+    cursor: (i16, i16),
+    // Changed since it was last saved
+    unsaved: bool,
+    // Quit was pressed with unsaved changes and has to be pressed again
+    confirm_quit: bool,
+    // What happened with the last key, like the level being saved or why it couldn't be
+    message: Option<(String, DrawColor)>,
+}
+
+impl EditorScene {
+    // Edit `level`, saving it to `path`. `unsaved` is true for a level that isn't in a file yet.
+    pub fn new(level: Level, path: PathBuf, unsaved: bool) -> Self {
+        EditorScene {
+            level,
+            path,
+            cursor: (0, 0),
+            unsaved,
+            confirm_quit: false,
+            message: None,
+        }
+    }
+
+    fn move_cursor(&mut self, direction: Direction) {
+        let (dx, dy) = direction.offset();
+        self.cursor = (
+            (self.cursor.0 + dx).clamp(0, self.level.columns() as i16 - 1),
+            (self.cursor.1 + dy).clamp(0, self.level.rows() as i16 - 1),
+        );
+    }
+
+    fn toggle_wall(&mut self) {
+        let (x, y) = self.cursor;
+        if self.level.snake_start().any(|cell| cell == self.cursor) {
+            self.message = Some(("The snake starts here".to_string(), DrawColor::Red));
+            return;
+        }
+        self.level.set_wall(x, y, !self.level.is_wall(x, y));
+        self.unsaved = true;
+    }
+
+    // Put the exit under the cursor, or take it away if it's there already
+    fn toggle_exit(&mut self) {
+        if self.level.snake_start().any(|cell| cell == self.cursor) {
+            self.message = Some(("The snake starts here".to_string(), DrawColor::Red));
+            return;
+        }
+        let exit = Some(self.cursor).filter(|cursor| self.level.exit() != Some(*cursor));
+        self.level.set_exit(exit);
+        self.unsaved = true;
+    }
+
+    // Move the head of the snake to the cursor, or turn it if it's there already. The snake stays
+    // where it was if it doesn't fit.
+    fn move_start(&mut self) {
+        let (x, y) = self.cursor;
+        let previous = self.level.clone();
+        let head = self.level.snake_start().next();
+        let direction = self.level.start_direction();
+        // Try every way round until the snake fits, starting with the next one when turning
+        let mut next = if head == Some(self.cursor) {
+            clockwise(direction)
+        } else {
+            direction
+        };
+        for _ in 0..4 {
+            self.level.set_start(x, y, next);
+            if self.level.check().is_ok() {
+                self.unsaved = true;
+                return;
+            }
+            next = clockwise(next);
+        }
+        self.level = previous;
+        self.message = Some(("The snake doesn't fit there".to_string(), DrawColor::Red));
+    }
+
+    fn save(&mut self) {
+        let saved = self.level.check().and_then(|_| self.level.save(&self.path));
+        self.message = Some(match saved {
+            Ok(()) => {
+                self.unsaved = false;
+                (format!("Saved {}", self.path.display()), DrawColor::Green)
+            }
+            Err(err) => (err, DrawColor::Red),
+        });
+    }
+
+    // Play the level as it is now. Restarting plays it again and the game over screen comes back
+    // here.
+    fn play_test(&mut self, context: &mut Context) -> Transition {
+        if let Err(err) = self.level.check() {
+            self.message = Some((err, DrawColor::Red));
+            return Transition::Stay;
+        }
+        context.config.level = Some(self.level.clone());
+        match PlayingScene::new(context) {
+            Some(playing) => Transition::Push(Box::new(playing)),
+            None => Transition::Stay,
+        }
+    }
+}
+
+impl Scene for EditorScene {
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let InputEvent::Key(key) = input else {
+            return Transition::Stay;
+        };
+        let confirm_quit = std::mem::take(&mut self.confirm_quit);
+        self.message = None;
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('s') => self.save(),
+                KeyCode::Char('t') => return self.play_test(context),
+                _ => {}
+            }
+            return Transition::Stay;
+        }
+        let command = context.config.keys.command_for_key(key);
+        let arrow = match key.code {
+            KeyCode::Up => Some(Direction::North),
+            KeyCode::Right => Some(Direction::East),
+            KeyCode::Down => Some(Direction::South),
+            KeyCode::Left => Some(Direction::West),
+            _ => None,
+        };
+        if let Some(direction) = arrow.or(match command {
+            Some(Command::Turn(direction)) => Some(direction),
+            _ => None,
+        }) {
+            self.move_cursor(direction);
+            return Transition::Stay;
+        }
+        match (key.code, command) {
+            (KeyCode::Esc, _) | (_, Some(Command::Quit)) => {
+                if !self.unsaved || confirm_quit {
+                    return Transition::Quit;
+                }
+                self.confirm_quit = true;
+                self.message = Some((
+                    "The level isn't saved. Press Esc again to quit anyway.".to_string(),
+                    DrawColor::Red,
+                ));
+            }
+            (KeyCode::Char(' '), _) | (_, Some(Command::Start)) => self.toggle_wall(),
+            (KeyCode::Enter, _) | (_, Some(Command::Restart)) => self.move_start(),
+            (KeyCode::Char('e' | 'E'), _) => self.toggle_exit(),
+            _ => {}
+        }
+        Transition::Stay
+    }
+
+    // Draw the level with the cursor on it, and what the keys do along the bottom of the screen
+    fn draw(&self, context: &mut Context) {
+        let level = &self.level;
+        let theme = context.config.theme;
+        let screen = &mut context.screen;
+        let Some(board_view) = BoardView::fit_board(level.columns(), level.rows(), theme, screen)
+        else {
+            draw_screen_too_small(
+                BoardView::screen_size_for(level.columns(), level.rows(), theme),
+                screen,
+            );
+            return;
+        };
+        board_view.draw_level(level, screen);
+        board_view.draw_cursor(self.cursor.0, self.cursor.1, screen);
+        let (message, color) = self.message.clone().unwrap_or_else(|| {
+            let unsaved = if self.unsaved { " (not saved)" } else { "" };
+            (
+                format!("Editing {}{}", self.path.display(), unsaved),
+                DrawColor::White,
+            )
+        });
+        let bottom = screen.height().saturating_sub(2);
+        screen.update_with_string(2, bottom.saturating_sub(1), message, color);
+        screen.update_with_string(
+            2,
+            bottom,
+            "Space wall  E exit  Enter snake  Ctrl+S save  Ctrl+T play  Esc quit".to_string(),
+            DrawColor::White,
+        );
+    }
+}
@@ -1,16 +1,20 @@
 // Copyright (c) 2022 DanWillans
-use crate::{DrawColor, DrawScreen};
-use rand::Rng;
+use crate::grid::OccupancyGrid;
+use crate::Level;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::collections::VecDeque;
 
-struct SnakeElement {
-    x: i16,
-    y: i16,
-    character: char,
-    color: DrawColor,
+// How many turns can be buffered ahead of the snake. One is applied per step.
+const MAX_QUEUED_TURNS: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnakeElement {
+    pub x: i16,
+    pub y: i16,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Direction {
     North,
     East,
@@ -18,11 +22,71 @@ pub enum Direction {
     South,
 }
 
+impl Direction {
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+            Direction::South => Direction::North,
+        }
+    }
+
+    // How far one step in this direction moves along x and y
+    pub fn offset(&self) -> (i16, i16) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+}
+
+// The outcome of advancing the GameBoard by a single step
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    // The snake moved without eating anything
+    Moved,
+    // The snake ate the food and the score went up
+    AteFood,
+    // The snake head left the board. The game is now over. Never happens on a board that wraps.
+    HitBorder,
+    // The snake head ran into its own body. The game is now over
+    HitSelf,
+    // The snake head ran into one of the level's walls. The game is now over
+    HitWall,
+    // The snake ate the food and there's nowhere left to put more. The game is won
+    Won,
+    // The snake went through the open exit, or ate all the food a level without an exit asks for.
+    // The level is finished
+    LevelComplete,
+    // The game was already over so nothing changed
+    Inactive,
+}
+
+impl GameEvent {
+    pub fn is_game_over(&self) -> bool {
+        matches!(
+            self,
+            GameEvent::HitBorder
+                | GameEvent::HitSelf
+                | GameEvent::HitWall
+                | GameEvent::Won
+                | GameEvent::LevelComplete
+        )
+    }
+}
+
+#[derive(Clone)]
 struct Snake {
     // Front of VecDeque is always the head of the snake
     // Back of VecDeque is always the tail of the snake
     elements: VecDeque<SnakeElement>,
+    // The direction the snake moved in on the last step
     direction: Direction,
+    // Turns waiting to be applied, oldest first
+    queued_turns: VecDeque<Direction>,
 }
 
 impl Snake {
@@ -34,399 +98,233 @@ impl Snake {
     }
 }
 
+// The GameBoard holds the complete state of a game and knows nothing about the terminal.
+// Positions are cell co-ordinates relative to the top left of the playing area (0,0) and
+// the board is `columns` cells wide and `rows` cells high. Drawing is handled by BoardView.
+// Food placement uses an RNG owned by the board so a game is fully reproducible from its seed.
+// The board's size, walls and where the snake starts come from a Level. `occupied` mirrors the
+// snake, the walls and the exit so food is only ever placed on a free cell.
+// On a board that wraps the snake leaves one edge and comes back in at the opposite one, so its
+// positions always stay on the board.
+#[derive(Clone)]
 pub struct GameBoard {
     snake: Snake,
-    width: u16,
-    height: u16,
-    position: (u16, u16),
-    old_food_position: (i16, i16),
+    occupied: OccupancyGrid,
+    seed: u64,
+    rng: ChaCha8Rng,
+    level: Level,
+    columns: u16,
+    rows: u16,
+    wrap: bool,
+    // Positions of food that has been eaten but not yet added to the tail, oldest first
+    eaten_food: VecDeque<(i16, i16)>,
     food_position: (i16, i16),
     game_active: bool,
-    add_food_to_snake: bool,
-    score: u16,
-}
-
-pub fn draw_title_screen(position: (u16, u16), screen: &mut DrawScreen) {
-    screen.update_with_string(
-        position.0 + 24,
-        position.1 + 15,
-        "Press the space bar to play.".to_string(),
-        DrawColor::White,
-    );
-    let r_datum = position;
-    screen.update(r_datum.0, r_datum.1, '╔', DrawColor::GameBorder);
-    screen.update(r_datum.0, r_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(r_datum.0, r_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(r_datum.0, r_datum.1 + 3, '╠', DrawColor::GameBorder);
-    screen.update(r_datum.0, r_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(r_datum.0, r_datum.1 + 5, '╙', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 1, r_datum.1, '═', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 2, r_datum.1, '═', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 3, r_datum.1, '═', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 4, r_datum.1, '═', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 5, r_datum.1, '╗', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 5, r_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 5, r_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 5, r_datum.1 + 3, '╣', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 5, r_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 5, r_datum.1 + 5, '╙', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 1, r_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 2, r_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 3, r_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(r_datum.0 + 4, r_datum.1 + 3, '═', DrawColor::GameBorder);
-    let u_datum = (r_datum.0 + 7, r_datum.1);
-    screen.update(u_datum.0, u_datum.1, '╓', DrawColor::GameBorder);
-    screen.update(u_datum.0, u_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(u_datum.0, u_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(u_datum.0, u_datum.1 + 3, '║', DrawColor::GameBorder);
-    screen.update(u_datum.0, u_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(u_datum.0, u_datum.1 + 5, '╚', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 1, u_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 2, u_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 3, u_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 4, u_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 5, u_datum.1 + 5, '╝', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 5, u_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 5, u_datum.1 + 3, '║', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 5, u_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 5, u_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(u_datum.0 + 5, u_datum.1, '╖', DrawColor::GameBorder);
-    let s_datum = (u_datum.0 + 7, u_datum.1);
-    screen.update(s_datum.0 + 5, s_datum.1, '╕', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 4, s_datum.1, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 3, s_datum.1, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 2, s_datum.1, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 1, s_datum.1, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0, s_datum.1, '╔', DrawColor::GameBorder);
-    screen.update(s_datum.0, s_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(s_datum.0, s_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(s_datum.0, s_datum.1 + 3, '╚', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 1, s_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 2, s_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 3, s_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 4, s_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 5, s_datum.1 + 3, '╗', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 5, s_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 5, s_datum.1 + 5, '║', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 5, s_datum.1 + 5, '╝', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 4, s_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 3, s_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 2, s_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0 + 1, s_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(s_datum.0, s_datum.1 + 5, '╘', DrawColor::GameBorder);
-    let t_datum = (s_datum.0 + 7, s_datum.1);
-    screen.update(t_datum.0, t_datum.1, '╒', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 1, t_datum.1, '═', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 2, t_datum.1, '═', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 3, t_datum.1, '╦', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 4, t_datum.1, '═', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 5, t_datum.1, '═', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 6, t_datum.1, '╕', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 3, t_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 3, t_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 3, t_datum.1 + 3, '║', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 3, t_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(t_datum.0 + 3, t_datum.1 + 5, '╨', DrawColor::GameBorder);
-    let y_datum = (t_datum.0 + 8, t_datum.1);
-    screen.update(y_datum.0, y_datum.1, '╓', DrawColor::GameBorder);
-    screen.update(y_datum.0, y_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(y_datum.0, y_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(y_datum.0, y_datum.1 + 3, '╚', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 1, y_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 2, y_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 3, y_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 4, y_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 5, y_datum.1 + 3, '╣', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 5, y_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 5, y_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 5, y_datum.1 + 0, '╖', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 5, y_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(y_datum.0 + 5, y_datum.1 + 5, '╨', DrawColor::GameBorder);
-    let sn_datum = (y_datum.0 + 12, y_datum.1);
-    screen.update(sn_datum.0 + 5, sn_datum.1, '╕', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 4, sn_datum.1, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 3, sn_datum.1, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 2, sn_datum.1, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 1, sn_datum.1, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0, sn_datum.1, '╔', DrawColor::GameBorder);
-    screen.update(sn_datum.0, sn_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(sn_datum.0, sn_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(sn_datum.0, sn_datum.1 + 3, '╚', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 1, sn_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 2, sn_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 3, sn_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 4, sn_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 5, sn_datum.1 + 3, '╗', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 5, sn_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 5, sn_datum.1 + 5, '║', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 5, sn_datum.1 + 5, '╝', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 4, sn_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 3, sn_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 2, sn_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0 + 1, sn_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(sn_datum.0, sn_datum.1 + 5, '╘', DrawColor::GameBorder);
-    let n_datum = (sn_datum.0 + 7, sn_datum.1);
-    screen.update(n_datum.0, n_datum.1, '╔', DrawColor::GameBorder);
-    screen.update(n_datum.0, n_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(n_datum.0, n_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(n_datum.0, n_datum.1 + 3, '║', DrawColor::GameBorder);
-    screen.update(n_datum.0, n_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(n_datum.0, n_datum.1 + 5, '╙', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 1, n_datum.1, '═', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 2, n_datum.1, '═', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 3, n_datum.1, '═', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 4, n_datum.1, '═', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 5, n_datum.1, '╗', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 5, n_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 5, n_datum.1 + 3, '║', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 5, n_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 5, n_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(n_datum.0 + 5, n_datum.1 + 5, '╙', DrawColor::GameBorder);
-    let a_datum = (n_datum.0 + 7, n_datum.1);
-    screen.update(a_datum.0, a_datum.1, '╔', DrawColor::GameBorder);
-    screen.update(a_datum.0, a_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(a_datum.0, a_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(a_datum.0, a_datum.1 + 3, '╠', DrawColor::GameBorder);
-    screen.update(a_datum.0, a_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(a_datum.0, a_datum.1 + 5, '╙', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 1, a_datum.1, '═', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 2, a_datum.1, '═', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 3, a_datum.1, '═', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 4, a_datum.1, '═', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 5, a_datum.1, '╗', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 5, a_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 5, a_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 5, a_datum.1 + 3, '╣', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 5, a_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 5, a_datum.1 + 5, '╜', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 1, a_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 2, a_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 3, a_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(a_datum.0 + 4, a_datum.1 + 3, '═', DrawColor::GameBorder);
-    let k_datum = (a_datum.0 + 7, a_datum.1);
-    screen.update(k_datum.0, k_datum.1, '╓', DrawColor::GameBorder);
-    screen.update(k_datum.0, k_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(k_datum.0, k_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(k_datum.0, k_datum.1 + 3, '╠', DrawColor::GameBorder);
-    screen.update(k_datum.0, k_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(k_datum.0, k_datum.1 + 5, '╙', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 5, k_datum.1, '╓', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 5, k_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 5, k_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 5, k_datum.1 + 3, '╣', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 5, k_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 5, k_datum.1 + 5, '╙', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 1, k_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 2, k_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 3, k_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(k_datum.0 + 4, k_datum.1 + 3, '═', DrawColor::GameBorder);
-    let e_datum = (k_datum.0 + 7, k_datum.1);
-    screen.update(e_datum.0, e_datum.1, '╔', DrawColor::GameBorder);
-    screen.update(e_datum.0, e_datum.1 + 1, '║', DrawColor::GameBorder);
-    screen.update(e_datum.0, e_datum.1 + 2, '║', DrawColor::GameBorder);
-    screen.update(e_datum.0, e_datum.1 + 3, '╠', DrawColor::GameBorder);
-    screen.update(e_datum.0, e_datum.1 + 4, '║', DrawColor::GameBorder);
-    screen.update(e_datum.0, e_datum.1 + 5, '╙', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 1, e_datum.1, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 2, e_datum.1, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 3, e_datum.1, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 4, e_datum.1, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 5, e_datum.1, '╕', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 5, e_datum.1 + 3, '╡', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 5, e_datum.1 + 5, '╛', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 1, e_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 2, e_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 3, e_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 4, e_datum.1 + 3, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 1, e_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 2, e_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 3, e_datum.1 + 5, '═', DrawColor::GameBorder);
-    screen.update(e_datum.0 + 4, e_datum.1 + 5, '═', DrawColor::GameBorder);
+    score: u32,
+    // Number of steps the snake has moved
+    steps: u32,
 }
 
-// Because we use larger unicode characters for the snake and they take up two spaces we'll need to alter the game board in comparison to the DrawScreen.
-// The GameBoard is half the resolution in the x direction than the game board. When drawing to the board use normal x, y co-ordinates relative to the top left of the GameBoard(0,0).
-// The drawing functions will correct the positions relative to the DrawScreen.
 impl GameBoard {
-    pub fn new(position: (u16, u16), width: u16, height: u16, screen: &mut DrawScreen) -> Self {
+    // Create a board for `level` with a random seed. Level::empty is the open board the game has
+    // always been played on.
+    pub fn new(level: &Level) -> Self {
+        GameBoard::with_seed(level, rand::thread_rng().gen())
+    }
+
+    // Create a board for `level` whose food placement is determined by `seed`
+    pub fn with_seed(level: &Level, seed: u64) -> Self {
+        let (columns, rows) = (level.columns(), level.rows());
         // Initialise game board
         let mut game_board = GameBoard {
             snake: Snake {
                 elements: VecDeque::new(),
                 direction: Direction::East,
+                queued_turns: VecDeque::new(),
             },
-            width,
-            height,
-            position,
-            old_food_position: (-1, -1),
+            occupied: OccupancyGrid::new(columns, rows),
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            level: level.clone(),
+            columns,
+            rows,
+            wrap: false,
+            eaten_food: VecDeque::new(),
             food_position: (-1, -1),
             game_active: true,
-            add_food_to_snake: false,
             score: 0,
+            steps: 0,
         };
 
-        // Initialise snake elements
-        game_board.snake.elements.push_back(SnakeElement {
-            x: 2,
-            y: 0,
-            character: '🙂',
-            color: DrawColor::Green,
-        });
-        game_board.snake.elements.push_back(SnakeElement {
-            x: 1,
-            y: 0,
-            character: '🐍',
-            color: DrawColor::Green,
-        });
-        game_board.snake.elements.push_back(SnakeElement {
-            x: 0,
-            y: 0,
-            character: '🐍',
-            color: DrawColor::Green,
-        });
-
-        game_board.draw_border(screen);
+        // Walls take up their cells for the whole game, and so does the exit
+        for (x, y) in level.walls() {
+            game_board.occupied.occupy(x, y);
+        }
+        if let Some((x, y)) = game_board.exit() {
+            game_board.occupied.occupy(x, y);
+        }
+
+        // Initialise snake elements where the level says the snake starts
+        game_board.snake.direction = level.start_direction();
+        for (x, y) in level.snake_start() {
+            game_board.push_tail(SnakeElement { x, y });
+        }
+
         game_board.create_new_food();
-        game_board.draw_food(screen);
-        game_board.draw_snake(screen);
 
         game_board
     }
 
-    fn draw_border(&self, screen: &mut DrawScreen) {
-        // Update border of the draw screen
-        // Draw corners of the draw screen
-        screen.update(self.position.0, self.position.1, '╔', DrawColor::GameBorder);
-        screen.update(
-            self.position.0 + self.width - 1,
-            self.position.1,
-            '╗',
-            DrawColor::GameBorder,
-        );
-        screen.update(
-            self.position.0 + self.width - 1,
-            self.position.1 + self.height,
-            '╝',
-            DrawColor::GameBorder,
-        );
-        screen.update(self.position.0, self.position.1 + self.height, '╚', DrawColor::GameBorder);
-        // Draw left and right borders.
-        // Divide by 2 because the character we're using is double length
-        for i in 1..self.height {
-            screen.update(self.position.0, self.position.1 + i, '║', DrawColor::GameBorder);
-            screen.update(
-                self.position.0 + self.width - 1,
-                self.position.1 + i,
-                '║',
-                DrawColor::GameBorder,
-            );
-        }
-        // Draw top and bottom border
-        for i in 1..self.width - 1 {
-            screen.update(self.position.0 + i, self.position.1, '═', DrawColor::GameBorder);
-            screen.update(
-                self.position.0 + i,
-                self.position.1 + self.height,
-                '═',
-                DrawColor::GameBorder,
-            );
-        }
-    }
-
-    fn clear_game_panel(&self, screen: &mut DrawScreen) {
-        for i in 1..self.height {
-            for j in 1..self.width - 1 {
-                screen.update(
-                    self.position.0 + j,
-                    self.position.1 + i,
-                    ' ',
-                    DrawColor::White,
-                );
-            }
+    // Make the edges of the board wrap around instead of ending the game
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+
+    // The name of the game mode, which is saved with high scores and replays
+    pub fn mode(&self) -> &'static str {
+        if self.wrap {
+            "wrap"
+        } else {
+            "classic"
         }
     }
 
-    fn draw_element(&self, element: &SnakeElement, screen: &mut DrawScreen) {
-        screen.update(
-            self.position.0 + (element.x as u16 * 2 + 1),
-            self.position.1 + (element.y as u16 + 1),
-            element.character,
-            element.color,
-        );
+    pub fn level(&self) -> &Level {
+        &self.level
     }
 
-    // Create new random food position
-    fn create_new_food(&mut self) {
-        let mut rng = rand::thread_rng();
-        self.food_position.0 = rng.gen_range(0..((self.width - 2) / 2)) as i16;
-        self.food_position.1 = rng.gen_range(0..self.height - 2) as i16;
+    pub fn columns(&self) -> u16 {
+        self.columns
     }
 
-    // Draw a piece of food on the game board
-    fn draw_food(&mut self, screen: &mut DrawScreen) {
-        let element = SnakeElement {
-            x: self.food_position.0,
-            y: self.food_position.1,
-            character: '🍎',
-            color: DrawColor::Red,
-        };
-        self.draw_element(&element, screen);
+    pub fn rows(&self) -> u16 {
+        self.rows
     }
 
-    // Draw the snake on the game board
-    fn draw_snake(&self, screen: &mut DrawScreen) {
-        for element in &self.snake.elements {
-            self.draw_element(element, screen);
-        }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    // How many steps the game has lasted
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    // False once the snake has collided with something or filled the board
+    pub fn is_active(&self) -> bool {
+        self.game_active
+    }
+
+    // Where the exit is, if the level has one that opens
+    pub fn exit(&self) -> Option<(i16, i16)> {
+        self.level.target().and(self.level.exit())
+    }
+
+    // True once enough food has been eaten to open the exit
+    pub fn is_exit_open(&self) -> bool {
+        self.level
+            .target()
+            .is_some_and(|target| self.score >= u32::from(target))
+    }
+
+    // True if the snake filled the board so no more food could be placed
+    pub fn has_won(&self) -> bool {
+        !self.game_active && self.occupied.free_cells() == 0
+    }
+
+    // True if the cell is on the board and neither the snake nor a wall is on it
+    pub fn is_free(&self, x: i16, y: i16) -> bool {
+        self.occupied.is_free(x, y)
+    }
+
+    pub fn food_position(&self) -> (i16, i16) {
+        self.food_position
+    }
+
+    // Iterate over the snake from head to tail
+    pub fn snake_elements(&self) -> impl Iterator<Item = &SnakeElement> {
+        self.snake.elements.iter()
+    }
+
+    pub fn snake_head(&self) -> Option<&SnakeElement> {
+        self.snake.get_head_element()
     }
 
-    // Remove tail SnakeElement from GameBoard and Snake
-    fn remove_tail_element(&mut self, screen: &mut DrawScreen) {
-        if let Some(element) = self.snake.elements.back_mut() {
-            element.character = ' ';
+    pub fn snake_len(&self) -> usize {
+        self.snake.elements.len()
+    }
+
+    pub fn snake_direction(&self) -> Direction {
+        self.snake.direction
+    }
+
+    // Create new random food position on a cell the snake isn't using.
+    // Returns false if the board is full and there's nowhere to put it.
+    fn create_new_food(&mut self) -> bool {
+        match self.occupied.random_free_cell(&mut self.rng) {
+            Some(position) => {
+                self.food_position = position;
+                true
+            }
+            None => {
+                self.food_position = (-1, -1);
+                false
+            }
         }
-        if let Some(element) = self.snake.elements.back() {
-            self.draw_element(element, screen);
+    }
+
+    // Where a step in `direction` from (x, y) ends up. Off the board unless the board wraps.
+    pub fn next_position(&self, x: i16, y: i16, direction: Direction) -> (i16, i16) {
+        let (dx, dy) = direction.offset();
+        let (x, y) = (x + dx, y + dy);
+        if self.wrap {
+            (
+                x.rem_euclid(self.columns as i16),
+                y.rem_euclid(self.rows as i16),
+            )
+        } else {
+            (x, y)
         }
-        self.snake.elements.pop_back();
     }
 
-    fn update_snake_position(&mut self, screen: &mut DrawScreen) {
-        self.remove_tail_element(screen);
+    // Add an element to the tail of the snake and mark its cell as occupied
+    fn push_tail(&mut self, element: SnakeElement) {
+        self.occupied.occupy(element.x, element.y);
+        self.snake.elements.push_back(element);
+    }
 
-        // Update character of the head as we'll be adding a new head onto the front based
-        // on direction
-        if let Some(element) = self.snake.elements.front_mut() {
-            element.character = '🐍';
+    fn update_snake_position(&mut self) {
+        if let Some(tail) = self.snake.elements.pop_back() {
+            self.occupied.release(tail.x, tail.y);
         }
 
-        // Helper closure for adding to the head
-        let mut add_head = |x: i16, y: i16| {
-            if let Some(head_pos) = self.snake.elements.front() {
-                self.snake.elements.push_front(SnakeElement {
-                    x: (head_pos.x as i16 + x),
-                    y: (head_pos.y as i16 + y),
-                    character: '🙂',
-                    color: DrawColor::Green,
-                });
-            }
+        let Some(head_pos) = self.snake.get_head_element() else {
+            return;
         };
 
         // Add new Head depending on direction
-        match self.snake.direction {
-            Direction::North => add_head(0, -1),
-            Direction::East => add_head(1, 0),
-            Direction::South => add_head(0, 1),
-            Direction::West => add_head(-1, 0),
-        }
+        let (x, y) = self.next_position(head_pos.x, head_pos.y, self.snake.direction);
+        let head = SnakeElement { x, y };
+        self.occupied.occupy(head.x, head.y);
+        self.snake.elements.push_front(head);
     }
 
-    // Returns true if the snake head collides with the border
-    fn check_border_collision(&self) -> bool {
+    // Returns true if the snake head collides with the border. The head never leaves a board that
+    // wraps so this is always false for one.
+    pub fn check_border_collision(&self) -> bool {
         if let Some(head) = self.snake.get_head_element() {
             if head.x < 0
-                || head.x > (self.width as i16 - 2) / 2 - 1
-                || head.y > self.height as i16 - 2
+                || head.x > self.columns as i16 - 1
+                || head.y > self.rows as i16 - 1
                 || head.y < 0
             {
                 return true;
@@ -435,8 +333,27 @@ impl GameBoard {
         false
     }
 
+    // Returns true if the snake head runs into a wall or the exit while it's shut
+    pub fn check_wall_collision(&self) -> bool {
+        match self.snake.get_head_element() {
+            Some(head) => {
+                self.level.is_wall(head.x, head.y)
+                    || (self.exit() == Some((head.x, head.y)) && !self.is_exit_open())
+            }
+            None => false,
+        }
+    }
+
+    // Returns true if the snake head has gone through the open exit
+    pub fn check_exit_reached(&self) -> bool {
+        match self.snake.get_head_element() {
+            Some(head) => self.is_exit_open() && self.exit() == Some((head.x, head.y)),
+            None => false,
+        }
+    }
+
     // Returns true if snake head collides with it's own body
-    fn check_self_collision(&self) -> bool {
+    pub fn check_self_collision(&self) -> bool {
         let x;
         let y;
         if let Some(head) = self.snake.get_head_element() {
@@ -453,108 +370,307 @@ impl GameBoard {
         false
     }
 
-    fn check_food_collision(&mut self) -> bool {
-        let x;
-        let y;
+    // Returns true if the snake head is on the food
+    pub fn check_food_collision(&self) -> bool {
         if let Some(head) = self.snake.get_head_element() {
-            x = head.x;
-            y = head.y;
-        } else {
+            return head.x == self.food_position.0 && head.y == self.food_position.1;
+        }
+        false
+    }
+
+    // Queue a turn to be applied on a following step. Turns are applied one per step so quick
+    // combinations like up-then-left happen over consecutive steps.
+    // Returns false if the turn was ignored because it would reverse the snake onto itself,
+    // wouldn't change direction, or the queue is full.
+    pub fn update_snake_direction(&mut self, direction: Direction) -> bool {
+        let previous = *self
+            .snake
+            .queued_turns
+            .back()
+            .unwrap_or(&self.snake.direction);
+        if direction == previous
+            || direction == previous.opposite()
+            || self.snake.queued_turns.len() >= MAX_QUEUED_TURNS
+        {
             return false;
         }
-        if x == self.food_position.0 && y == self.food_position.1 {
-            self.old_food_position.0 = x;
-            self.old_food_position.1 = y;
-            return true;
+        self.snake.queued_turns.push_back(direction);
+        true
+    }
+
+    fn add_food_to_snake(&mut self) {
+        // Check if the tail is at the oldest eaten food position
+        if let (Some(&tail), Some(&food)) = (self.snake.get_tail_element(), self.eaten_food.front())
+        {
+            if tail.x == food.0 && tail.y == food.1 {
+                self.push_tail(tail);
+                self.eaten_food.pop_front();
+            }
         }
-        false
     }
 
-    pub fn update_snake_direction(&mut self, direction: Direction) {
-        self.snake.direction = direction
+    // Advance the game by a single step. If a direction is given it's queued as if it was passed to
+    // update_snake_direction, then the oldest queued turn is applied before the snake moves.
+    // This moves and grows the snake, detects collisions and respawns food.
+    pub fn step(&mut self, direction: Option<Direction>) -> GameEvent {
+        if !self.game_active {
+            return GameEvent::Inactive;
+        }
+
+        if let Some(direction) = direction {
+            self.update_snake_direction(direction);
+        }
+        if let Some(direction) = self.snake.queued_turns.pop_front() {
+            self.snake.direction = direction;
+        }
+
+        self.update_snake_position();
+        self.steps += 1;
+
+        // Calculate if we've collided with the border
+        if self.check_border_collision() {
+            self.game_active = false;
+            return GameEvent::HitBorder;
+        }
+
+        // Calculate if we've run into a wall
+        if self.check_wall_collision() {
+            self.game_active = false;
+            return GameEvent::HitWall;
+        }
+
+        // Calculate if we've left through the exit
+        if self.check_exit_reached() {
+            self.game_active = false;
+            return GameEvent::LevelComplete;
+        }
+
+        // Calculate if we've collided with ourselves
+        if self.check_self_collision() {
+            self.game_active = false;
+            return GameEvent::HitSelf;
+        }
+
+        // Calculate if we've eaten an apple
+        let mut event = GameEvent::Moved;
+        if self.check_food_collision() {
+            // Remember where the food was so the tail can grow once it gets there
+            self.eaten_food.push_back(self.food_position);
+            // Update the score
+            self.score += 1;
+            event = GameEvent::AteFood;
+            // A level without an exit is finished once enough food is eaten
+            if self.level.exit().is_none() && self.is_exit_open() {
+                self.game_active = false;
+                return GameEvent::LevelComplete;
+            }
+            // Create new food. If there's no free cell left the snake has filled the board
+            if !self.create_new_food() {
+                self.game_active = false;
+                return GameEvent::Won;
+            }
+        }
+
+        // Check if we should add food to the tail of the snake
+        self.add_food_to_snake();
+
+        event
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn game_over(&mut self, screen: &mut DrawScreen) {
-        self.game_active = false;
-        let game_over_text = "Oh my goodness you did such a big lose! Press Enter to try again.";
-        let text_pos_x = self.position.0 + self.width / 2 - (game_over_text.len() / 2) as u16;
-        let text_pos_y = self.position.1 + self.height / 2;
-        screen.update_with_string(
-            text_pos_x,
-            text_pos_y,
-            game_over_text.to_string(),
-            DrawColor::Red,
-        );
+    // A 10x10 open board. The snake starts with its head at (2, 0) heading east.
+    fn board() -> GameBoard {
+        GameBoard::with_seed(&Level::empty(10, 10), 1)
     }
 
-    fn draw_score(&mut self, screen: &mut DrawScreen) {
-        screen.update_with_string(
-            2,
-            self.height / 2,
-            format!("Score: {}", self.score),
-            DrawColor::White,
-        )
+    // Put the food right in front of the snake and step onto it
+    fn eat(board: &mut GameBoard) -> GameEvent {
+        let head = board.snake_head().unwrap();
+        board.food_position = board.next_position(head.x, head.y, board.snake_direction());
+        board.step(None)
     }
 
-    fn add_food_to_snake(&mut self) {
-        // Check if the tail is at the old food position
-        if let Some(tail) = self.snake.get_tail_element() {
-            if tail.x == self.old_food_position.0 && tail.y == self.old_food_position.1 {
-                self.snake.elements.push_back(SnakeElement {
-                    x: tail.x,
-                    y: tail.y,
-                    character: '🐍',
-                    color: DrawColor::Green,
-                });
-                // Reset food collision
-                self.add_food_to_snake = false;
-            }
+    fn positions(board: &GameBoard) -> Vec<(i16, i16)> {
+        board.snake_elements().map(|e| (e.x, e.y)).collect()
+    }
+
+    #[test]
+    fn starts_where_the_level_says() {
+        let board = board();
+        assert_eq!(positions(&board), vec![(2, 0), (1, 0), (0, 0)]);
+        assert_eq!(board.snake_direction(), Direction::East);
+        assert!(board.is_active());
+        assert!(!board.check_border_collision());
+        assert!(!board.check_self_collision());
+    }
+
+    #[test]
+    fn same_seed_places_the_same_food() {
+        let mut a = board();
+        let mut b = board();
+        for _ in 0..5 {
+            assert_eq!(a.food_position(), b.food_position());
+            assert_eq!(a.step(None), b.step(None));
         }
     }
 
-    pub fn update(&mut self, screen: &mut DrawScreen) {
-        if self.game_active {
-            self.update_snake_position(screen);
+    #[test]
+    fn food_never_starts_on_the_snake() {
+        for seed in 0..200 {
+            let board = GameBoard::with_seed(&Level::empty(4, 1), seed);
+            assert_eq!(board.food_position(), (3, 0));
+        }
+    }
 
-            // Calculate if we've collided with the border
-            if self.check_border_collision() {
-                self.game_over(screen);
-                return;
-            }
+    #[test]
+    fn moves_one_cell_a_step() {
+        let mut board = board();
+        board.food_position = (9, 9);
+        assert_eq!(board.step(None), GameEvent::Moved);
+        assert_eq!(positions(&board), vec![(3, 0), (2, 0), (1, 0)]);
+        assert_eq!(board.steps(), 1);
+    }
 
-            // Calculate if we've collided with ourselves
-            if self.check_self_collision() {
-                self.game_over(screen);
-                return;
-            }
+    #[test]
+    fn leaving_the_board_hits_the_border() {
+        let mut board = board();
+        assert_eq!(board.step(Some(Direction::North)), GameEvent::HitBorder);
+        assert!(board.check_border_collision());
+        assert!(!board.is_active());
+        assert_eq!(board.step(None), GameEvent::Inactive);
+    }
 
-            // Calculate if we've eaten an apple
-            if self.check_food_collision() {
-                // Update the score
-                self.score += 1;
-                // Draw new food
-                self.create_new_food();
-                self.add_food_to_snake = true;
-            }
+    #[test]
+    fn running_off_the_east_edge_hits_the_border() {
+        let mut board = board();
+        board.food_position = (0, 9);
+        let mut event = GameEvent::Moved;
+        for _ in 0..7 {
+            assert_eq!(event, GameEvent::Moved);
+            event = board.step(None);
+        }
+        assert_eq!(event, GameEvent::Moved);
+        assert_eq!(board.step(None), GameEvent::HitBorder);
+    }
 
-            // Check if we should add food to the tail of the snake
-            if self.add_food_to_snake {
-                self.add_food_to_snake();
-            }
+    #[test]
+    fn wrapping_board_has_no_border() {
+        let mut board = board().with_wrap(true);
+        board.food_position = (9, 9);
+        assert_eq!(board.step(Some(Direction::North)), GameEvent::Moved);
+        assert_eq!(board.snake_head(), Some(&SnakeElement { x: 2, y: 9 }));
+        assert!(!board.check_border_collision());
+    }
+
+    #[test]
+    fn eating_scores_and_grows_the_tail_when_it_reaches_the_food() {
+        let mut board = board();
+        board.food_position = (3, 0);
+        assert!(!board.check_food_collision());
+        board.food_position = (2, 0);
+        assert!(board.check_food_collision());
+        assert_eq!(eat(&mut board), GameEvent::AteFood);
+        assert_eq!(board.score(), 1);
+        assert_eq!(board.eaten_food, VecDeque::from([(3, 0)]));
+        assert_ne!(board.food_position(), (3, 0));
+        board.food_position = (9, 9);
+        // The tail only grows once it gets to where the food was eaten
+        assert_eq!(board.step(None), GameEvent::Moved);
+        assert_eq!(board.snake_len(), 3);
+        assert_eq!(board.step(None), GameEvent::Moved);
+        assert_eq!(board.snake_len(), 4);
+        assert_eq!(positions(&board), vec![(5, 0), (4, 0), (3, 0), (3, 0)]);
+        assert!(board.eaten_food.is_empty());
+        assert_eq!(board.step(None), GameEvent::Moved);
+        assert_eq!(positions(&board), vec![(6, 0), (5, 0), (4, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn running_into_itself_ends_the_game() {
+        let mut board = board();
+        for _ in 0..3 {
+            assert_eq!(eat(&mut board), GameEvent::AteFood);
+        }
+        board.food_position = (9, 9);
+        for _ in 0..4 {
+            board.step(None);
+        }
+        assert_eq!(board.snake_len(), 6);
+        assert!(!board.check_self_collision());
+        // Turning back round on itself a step at a time
+        board.update_snake_direction(Direction::South);
+        board.update_snake_direction(Direction::West);
+        board.update_snake_direction(Direction::North);
+        assert_eq!(board.step(None), GameEvent::Moved);
+        assert_eq!(board.step(None), GameEvent::Moved);
+        assert_eq!(board.step(None), GameEvent::HitSelf);
+        assert!(board.check_self_collision());
+        assert!(!board.is_active());
+    }
+
+    #[test]
+    fn moving_into_the_cell_the_tail_leaves_is_allowed() {
+        // A snake of four in a 2x2 square chases its own tail round forever
+        let mut board = GameBoard::with_seed(&Level::empty(4, 2), 1);
+        assert_eq!(eat(&mut board), GameEvent::AteFood);
+        board.food_position = (-1, -1);
+        board.update_snake_direction(Direction::South);
+        board.step(None);
+        board.update_snake_direction(Direction::West);
+        board.step(None);
+        assert_eq!(board.snake_len(), 4);
+        board.update_snake_direction(Direction::North);
+        board.step(None);
+        board.update_snake_direction(Direction::East);
+        assert_eq!(board.step(None), GameEvent::Moved);
+        assert!(board.is_active());
+    }
 
-            // Redrawing everything on every update seems excessive and normally you would only redraw
-            // what's changed. The terminal rendering produces unwanted artifacts due to the way the console
-            // processes some keys so to avoid these artifacts redraw everything.
-            self.clear_game_panel(screen);
-            self.draw_border(screen);
-            self.draw_score(screen);
-            self.draw_food(screen);
-            self.draw_snake(screen);
+    #[test]
+    fn reversing_or_repeating_a_direction_is_ignored() {
+        let mut board = board();
+        assert!(!board.update_snake_direction(Direction::West));
+        assert!(!board.update_snake_direction(Direction::East));
+        assert!(board.update_snake_direction(Direction::South));
+        // Turns are checked against the last queued one, not the way the snake is going
+        assert!(!board.update_snake_direction(Direction::North));
+        assert!(!board.update_snake_direction(Direction::South));
+        assert!(board.update_snake_direction(Direction::West));
+    }
+
+    #[test]
+    fn queues_up_to_three_turns_applied_one_a_step() {
+        let mut board = board().with_wrap(true);
+        board.food_position = (-1, -1);
+        assert!(board.update_snake_direction(Direction::South));
+        assert!(board.update_snake_direction(Direction::West));
+        assert!(board.update_snake_direction(Direction::North));
+        assert!(!board.update_snake_direction(Direction::East));
+        for direction in [Direction::South, Direction::West, Direction::North] {
+            board.step(None);
+            assert_eq!(board.snake_direction(), direction);
         }
+        board.step(None);
+        assert_eq!(board.snake_direction(), Direction::North);
     }
 
-    pub fn reset(&self, screen: &mut DrawScreen) -> Self {
-        self.clear_game_panel(screen);
-        GameBoard::new(self.position, self.width, self.height, screen)
+    #[test]
+    fn filling_the_board_wins() {
+        let mut board = GameBoard::with_seed(&Level::empty(4, 1), 1).with_wrap(true);
+        let mut event = GameEvent::Moved;
+        for _ in 0..100 {
+            event = board.step(None);
+            if event.is_game_over() {
+                break;
+            }
+        }
+        assert_eq!(event, GameEvent::Won);
+        assert!(board.has_won());
+        assert_eq!(board.food_position(), (-1, -1));
+        assert_eq!(board.step(None), GameEvent::Inactive);
     }
 }
@@ -0,0 +1,191 @@
+// Copyright (c) 2022 DanWillans
+use rand::Rng;
+
+// Sentinel stored in `slots` for cells that aren't in the free list
+const NOT_FREE: u32 = u32::MAX;
+
+// Tracks which cells of a board are occupied so that a random free cell can be picked in constant
+// time without scanning the snake.
+// `free` holds the index of every free cell in no particular order and `slots` maps each cell index
+// to where it lives in `free`. `occupants` counts how many things sit on a cell, which matters
+// because the snake briefly doubles up its tail when it grows.
+#[derive(Clone)]
+pub struct OccupancyGrid {
+    columns: u16,
+    rows: u16,
+    occupants: Vec<u8>,
+    free: Vec<u32>,
+    slots: Vec<u32>,
+}
+
+impl OccupancyGrid {
+    // Create a grid where every cell is free
+    pub fn new(columns: u16, rows: u16) -> Self {
+        let cells = columns as u32 * rows as u32;
+        OccupancyGrid {
+            columns,
+            rows,
+            occupants: vec![0; cells as usize],
+            free: (0..cells).collect(),
+            slots: (0..cells).collect(),
+        }
+    }
+
+    fn index(&self, x: i16, y: i16) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.columns as i16 || y >= self.rows as i16 {
+            return None;
+        }
+        Some(y as usize * self.columns as usize + x as usize)
+    }
+
+    // Mark a cell as occupied. Cells outside the grid are ignored.
+    pub fn occupy(&mut self, x: i16, y: i16) {
+        let Some(index) = self.index(x, y) else {
+            return;
+        };
+        self.occupants[index] = self.occupants[index].saturating_add(1);
+        if self.occupants[index] == 1 {
+            // Swap the last free cell into this cell's slot
+            let slot = self.slots[index] as usize;
+            let last = *self.free.last().expect("free cell list out of sync");
+            self.free.swap_remove(slot);
+            if last as usize != index {
+                self.slots[last as usize] = slot as u32;
+            }
+            self.slots[index] = NOT_FREE;
+        }
+    }
+
+    // Undo a previous occupy. Cells outside the grid are ignored.
+    pub fn release(&mut self, x: i16, y: i16) {
+        let Some(index) = self.index(x, y) else {
+            return;
+        };
+        if self.occupants[index] == 0 {
+            return;
+        }
+        self.occupants[index] -= 1;
+        if self.occupants[index] == 0 {
+            self.slots[index] = self.free.len() as u32;
+            self.free.push(index as u32);
+        }
+    }
+
+    // Returns true if the cell is inside the grid and nothing is on it
+    pub fn is_free(&self, x: i16, y: i16) -> bool {
+        match self.index(x, y) {
+            Some(index) => self.occupants[index] == 0,
+            None => false,
+        }
+    }
+
+    // Number of free cells left
+    pub fn free_cells(&self) -> usize {
+        self.free.len()
+    }
+
+    // Pick a uniformly random free cell, or None if the grid is full
+    pub fn random_free_cell<R: Rng>(&self, rng: &mut R) -> Option<(i16, i16)> {
+        if self.free.is_empty() {
+            return None;
+        }
+        let index = self.free[rng.gen_range(0..self.free.len())];
+        let columns = self.columns as u32;
+        Some(((index % columns) as i16, (index / columns) as i16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn new_grid_is_all_free() {
+        let grid = OccupancyGrid::new(4, 3);
+        assert_eq!(grid.free_cells(), 12);
+        assert!(grid.is_free(0, 0));
+        assert!(grid.is_free(3, 2));
+    }
+
+    #[test]
+    fn cells_outside_the_grid_are_never_free_and_are_ignored() {
+        let mut grid = OccupancyGrid::new(4, 3);
+        for (x, y) in [(-1, 0), (0, -1), (4, 0), (0, 3)] {
+            assert!(!grid.is_free(x, y));
+            grid.occupy(x, y);
+            grid.release(x, y);
+        }
+        assert_eq!(grid.free_cells(), 12);
+    }
+
+    #[test]
+    fn occupy_and_release() {
+        let mut grid = OccupancyGrid::new(4, 3);
+        grid.occupy(1, 2);
+        assert!(!grid.is_free(1, 2));
+        assert_eq!(grid.free_cells(), 11);
+        grid.release(1, 2);
+        assert!(grid.is_free(1, 2));
+        assert_eq!(grid.free_cells(), 12);
+        // Releasing a free cell does nothing
+        grid.release(1, 2);
+        assert_eq!(grid.free_cells(), 12);
+    }
+
+    #[test]
+    fn double_occupied_cell_stays_taken_until_both_are_released() {
+        // The snake's tail sits on the same cell twice for a step when it grows
+        let mut grid = OccupancyGrid::new(4, 3);
+        grid.occupy(2, 1);
+        grid.occupy(2, 1);
+        assert_eq!(grid.free_cells(), 11);
+        grid.release(2, 1);
+        assert!(!grid.is_free(2, 1));
+        assert_eq!(grid.free_cells(), 11);
+        grid.release(2, 1);
+        assert!(grid.is_free(2, 1));
+        assert_eq!(grid.free_cells(), 12);
+    }
+
+    #[test]
+    fn random_free_cell_only_picks_free_cells() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut grid = OccupancyGrid::new(4, 3);
+        // Occupy in an order that moves cells around the free list
+        for (x, y) in [(0, 0), (3, 2), (1, 1), (2, 0), (0, 2), (3, 1)] {
+            grid.occupy(x, y);
+        }
+        grid.release(1, 1);
+        for _ in 0..200 {
+            let (x, y) = grid.random_free_cell(&mut rng).unwrap();
+            assert!(grid.is_free(x, y));
+        }
+    }
+
+    #[test]
+    fn random_free_cell_finds_the_last_free_cell_and_none_when_full() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut grid = OccupancyGrid::new(2, 2);
+        grid.occupy(0, 0);
+        grid.occupy(1, 0);
+        grid.occupy(1, 1);
+        assert_eq!(grid.random_free_cell(&mut rng), Some((0, 1)));
+        grid.occupy(0, 1);
+        assert_eq!(grid.free_cells(), 0);
+        assert_eq!(grid.random_free_cell(&mut rng), None);
+        grid.release(1, 0);
+        assert_eq!(grid.random_free_cell(&mut rng), Some((1, 0)));
+    }
+
+    #[test]
+    fn random_free_cell_is_reproducible_from_a_seed() {
+        let grid = OccupancyGrid::new(10, 10);
+        let mut a = ChaCha8Rng::seed_from_u64(7);
+        let mut b = ChaCha8Rng::seed_from_u64(7);
+        for _ in 0..20 {
+            assert_eq!(grid.random_free_cell(&mut a), grid.random_free_cell(&mut b));
+        }
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright (c) 2022 DanWillans
+use crate::Direction;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+// Something the player asked the game to do
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Turn(Direction),
+    Start,
+    Restart,
+    // Freeze the game and open the pause menu
+    Pause,
+    Quit,
+}
+
+// Everything the input thread reports back to the game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    // A key was pressed. KeyBindings turns it into a Command, or it can be typed into a text field.
+    Key(KeyEvent),
+    // The terminal was resized to the given number of columns and rows
+    Resize(u16, u16),
+    // The terminal window lost focus, so the player has probably switched to something else
+    FocusLost,
+}
+
+// Which keys send which Commands. Letters match either case.
+// Ctrl+C always quits because raw mode stops the terminal turning it into SIGINT.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyBindings {
+    keys: Vec<(KeyCode, Command)>,
+}
+
+impl KeyBindings {
+    // No keys bound at all, only Ctrl+C works
+    pub fn empty() -> Self {
+        KeyBindings { keys: Vec::new() }
+    }
+
+    // Make `key` send `command`, replacing whatever it did before
+    pub fn bind(&mut self, key: KeyCode, command: Command) {
+        let key = normalise_key(key);
+        self.keys.retain(|(bound, _)| *bound != key);
+        self.keys.push((key, command));
+    }
+
+    // Remove every key that sends `command`
+    pub fn unbind(&mut self, command: Command) {
+        self.keys.retain(|(_, bound)| *bound != command);
+    }
+
+    // The keys that send `command`
+    pub fn keys_for(&self, command: Command) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keys
+            .iter()
+            .filter(move |(_, bound)| *bound == command)
+            .map(|(key, _)| *key)
+    }
+
+    // Translate a key press into a Command. Keys the game doesn't use return None.
+    pub fn command_for_key(&self, key: &KeyEvent) -> Option<Command> {
+        if key.kind == KeyEventKind::Release {
+            return None;
+        }
+        if is_ctrl_c(key) {
+            return Some(Command::Quit);
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+        let code = normalise_key(key.code);
+        self.keys
+            .iter()
+            .find(|(bound, _)| *bound == code)
+            .map(|(_, command)| *command)
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut bindings = KeyBindings::empty();
+        bindings.bind(KeyCode::Char('w'), Command::Turn(Direction::North));
+        bindings.bind(KeyCode::Char('d'), Command::Turn(Direction::East));
+        bindings.bind(KeyCode::Char('s'), Command::Turn(Direction::South));
+        bindings.bind(KeyCode::Char('a'), Command::Turn(Direction::West));
+        bindings.bind(KeyCode::Char(' '), Command::Start);
+        bindings.bind(KeyCode::Enter, Command::Restart);
+        bindings.bind(KeyCode::Char('p'), Command::Pause);
+        bindings.bind(KeyCode::Esc, Command::Pause);
+        bindings.bind(KeyCode::Char('q'), Command::Quit);
+        bindings
+    }
+}
+
+// Ctrl+C quits straight away wherever it's pressed, where the quit key may ask first
+pub fn is_ctrl_c(key: &KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c')
+}
+
+// Letters are bound in lower case so they work with caps lock or shift held
+fn normalise_key(key: KeyCode) -> KeyCode {
+    match key {
+        KeyCode::Char(character) => KeyCode::Char(character.to_ascii_lowercase()),
+        _ => key,
+    }
+}
+
+// Read a key name from the config file. Either a single character like "w" or the name of a key
+// like "space", "enter", "esc" or "up".
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut characters = name.chars();
+    if let (Some(character), None) = (characters.next(), characters.next()) {
+        return Some(normalise_key(KeyCode::Char(character)));
+    }
+    match name.to_ascii_lowercase().as_str() {
+        "space" => Some(KeyCode::Char(' ')),
+        "enter" | "return" => Some(KeyCode::Enter),
+        "esc" | "escape" => Some(KeyCode::Esc),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        _ => None,
+    }
+}
+
+// What a key press did to a line of text being typed in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextEdit {
+    Changed,
+    // Enter or Esc was pressed to finish typing
+    Finished,
+    // The key doesn't do anything to text
+    Ignored,
+}
+
+// Apply a key press to `text`. Printable characters are added until it's `max_length` characters
+// long and Backspace removes the last one.
+pub fn edit_text(text: &mut String, key: &KeyEvent, max_length: usize) -> TextEdit {
+    if key.kind == KeyEventKind::Release || key.modifiers.contains(KeyModifiers::CONTROL) {
+        return TextEdit::Ignored;
+    }
+    match key.code {
+        KeyCode::Char(character) if !character.is_control() => {
+            if text.chars().count() < max_length {
+                text.push(character);
+            }
+            TextEdit::Changed
+        }
+        KeyCode::Backspace => {
+            text.pop();
+            TextEdit::Changed
+        }
+        KeyCode::Enter | KeyCode::Esc => TextEdit::Finished,
+        _ => TextEdit::Ignored,
+    }
+}
+
+// Read events from the terminal on a background thread and send the key presses, resizes and focus
+// changes.
+// The terminal must be in raw mode for key presses to arrive without waiting for Enter.
+// The thread stops when the receiver is dropped or the terminal can't be read.
+pub fn spawn_input_thread() -> Receiver<InputEvent> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || loop {
+        let input = match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => Some(InputEvent::Key(key)),
+            Ok(Event::Resize(columns, rows)) => Some(InputEvent::Resize(columns, rows)),
+            Ok(Event::FocusLost) => Some(InputEvent::FocusLost),
+            Ok(_) => None,
+            Err(_) => break,
+        };
+        if let Some(input) = input {
+            if tx.send(input).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
@@ -0,0 +1,511 @@
+// Copyright (c) 2022 DanWillans
+use crate::Direction;
+use std::fs;
+use std::path::Path;
+
+// Levels are a picture of the board, one line per row. `#` is a wall and `.` or a space is an empty
+// cell. Lines starting with `;` are comments and rows shorter than the widest one are padded with
+// empty cells. The snake's head starts on `>`, `<`, `^` or `v`, pointing the way it sets off, with
+// the rest of the snake in the two cells behind it. Without one the snake starts in the top left
+// heading east, as if the third cell of the top row was a `>`.
+// `E` is the exit. In the campaign it's shut until enough food has been eaten and going through it
+// finishes the level. Outside the campaign it's an empty cell.
+//
+// ; A box with a gap in the middle
+// ..>.....
+// .##E.##.
+// ........
+
+// Length of the snake at the start of a game
+const START_LENGTH: i16 = 3;
+
+// The most cells a board can have, the same as a 1024x1024 board. The walls and the board's
+// OccupancyGrid each keep a few bytes for every cell.
+const MAX_CELLS: u32 = 1 << 20;
+
+// The levels that come with the game, each a name and its map
+const BUILT_IN_LEVELS: [(&str, &str); 3] = [
+    (
+        "box",
+        "\
+........................
+.######################.
+.#....................#.
+.#....................#.
+.#....................#.
+.#....................#.
+.#.........E..........#.
+.#....................#.
+.#....................#.
+.#....................#.
+.#....................#.
+.#....................#.
+.###########..#########.
+........................",
+    ),
+    (
+        "pillars",
+        "\
+........................
+........................
+...##......##......##...
+...##......##......##...
+........................
+........................
+........................
+...........E............
+........................
+........................
+...##......##......##...
+...##......##......##...
+........................
+........................",
+    ),
+    (
+        "cross",
+        "\
+........................
+........................
+...........##...........
+...........##...........
+...........##...........
+...........##...........
+....######.E..######....
+...........##...........
+...........##...........
+...........##...........
+...........##...........
+........................
+........................
+........................",
+    ),
+];
+
+// A board to play on: its size and where the walls are
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Level {
+    name: String,
+    columns: u16,
+    rows: u16,
+    // One for each cell, row by row, true where there's a wall. The game checks for walls every
+    // step so looking one up has to be quick.
+    walls: Vec<bool>,
+    // Where the snake's head starts and the way it's heading
+    start: (i16, i16),
+    direction: Direction,
+    exit: Option<(i16, i16)>,
+    // How much food opens the exit. None outside the campaign, where there's no exit.
+    target: Option<u16>,
+}
+
+// The character the start of the snake is drawn with in a level file
+fn start_marker(direction: Direction) -> char {
+    match direction {
+        Direction::North => '^',
+        Direction::East => '>',
+        Direction::South => 'v',
+        Direction::West => '<',
+    }
+}
+
+impl Level {
+    // An open board with no walls, the way the game has always been played
+    pub fn empty(columns: u16, rows: u16) -> Self {
+        Level {
+            name: String::new(),
+            columns,
+            rows,
+            walls: vec![false; columns as usize * rows as usize],
+            start: (START_LENGTH - 1, 0),
+            direction: Direction::East,
+            exit: None,
+            target: None,
+        }
+    }
+
+    // Check a board of `columns` by `rows` can be played. The snake starts three cells long so the
+    // board needs room for it and some food. Positions are i16s and every cell takes up memory, so
+    // it can't be too big either.
+    pub fn check_size(columns: u16, rows: u16) -> Result<(), String> {
+        if columns < 4 || rows < 1 {
+            return Err(format!("a {}x{} board is too small", columns, rows));
+        }
+        if columns > i16::MAX as u16
+            || rows > i16::MAX as u16
+            || columns as u32 * rows as u32 > MAX_CELLS
+        {
+            return Err(format!("a {}x{} board is too big", columns, rows));
+        }
+        Ok(())
+    }
+
+    // The same level with an exit that opens after eating `target` pieces of food. The level is
+    // finished as soon as the food is eaten if it has no exit.
+    pub fn with_target(mut self, target: u16) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    // The same level under a different name
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // Names of the built in levels
+    pub fn built_in_names() -> impl Iterator<Item = &'static str> {
+        BUILT_IN_LEVELS.into_iter().map(|(name, _)| name)
+    }
+
+    // Look up a built in level by name
+    pub fn by_name(name: &str) -> Option<Level> {
+        let (name, text) = BUILT_IN_LEVELS
+            .into_iter()
+            .find(|(level, _)| level.eq_ignore_ascii_case(name))?;
+        Level::parse(name, text).ok()
+    }
+
+    // A built in level if there's one called `value`, otherwise the level file at that path
+    pub fn by_name_or_file(value: &str) -> Result<Level, String> {
+        match Level::by_name(value) {
+            Some(level) => Ok(level),
+            None => Level::load(Path::new(value)),
+        }
+    }
+
+    // Load a level file. The level is named after the file.
+    pub fn load(path: &Path) -> Result<Level, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        let name = path
+            .file_stem()
+            .map_or("level".into(), |stem| stem.to_string_lossy());
+        Level::parse(&name, &text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn parse(name: &str, text: &str) -> Result<Level, String> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.starts_with(';'))
+            .collect();
+        // Blank lines after the map are ignored
+        let rows = lines
+            .iter()
+            .rposition(|line| !line.trim().is_empty())
+            .map_or(0, |last| last + 1);
+        let columns = lines[..rows]
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let size = |size: usize| u16::try_from(size).unwrap_or(u16::MAX);
+        Level::check_size(size(columns), size(rows))?;
+        let mut level = Level::empty(columns as u16, rows as u16).with_name(name);
+        let mut starts = 0;
+        let mut exits = 0;
+        for (y, line) in lines[..rows].iter().enumerate() {
+            for (x, cell) in line.chars().enumerate() {
+                let position = (x as i16, y as i16);
+                match cell {
+                    '#' => level.set_wall(position.0, position.1, true),
+                    '.' | ' ' => {}
+                    'E' => {
+                        exits += 1;
+                        level.exit = Some(position);
+                    }
+                    '^' | '>' | 'v' | '<' => {
+                        starts += 1;
+                        level.start = position;
+                        level.direction = match cell {
+                            '^' => Direction::North,
+                            '>' => Direction::East,
+                            'v' => Direction::South,
+                            _ => Direction::West,
+                        };
+                    }
+                    _ => {
+                        return Err(format!(
+                            "'{}' on row {} isn't a wall, an empty cell, the snake or the exit",
+                            cell,
+                            y + 1
+                        ))
+                    }
+                }
+            }
+        }
+        if starts > 1 {
+            return Err("the snake can only start in one place".to_string());
+        }
+        if exits > 1 {
+            return Err("there can only be one exit".to_string());
+        }
+        level.check()?;
+        Ok(level)
+    }
+
+    // Check the level can be played, which means the snake has to start on empty cells
+    pub fn check(&self) -> Result<(), String> {
+        let fits = self.snake_start().all(|(x, y)| {
+            x >= 0
+                && y >= 0
+                && x < self.columns as i16
+                && y < self.rows as i16
+                && !self.is_wall(x, y)
+                && self.exit != Some((x, y))
+        });
+        if !fits {
+            return Err("the snake has to start on empty cells inside the board".to_string());
+        }
+        Ok(())
+    }
+
+    // Save the level in the text format it's loaded from
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_text())
+            .map_err(|err| format!("Couldn't save {}: {}", path.display(), err))
+    }
+
+    // The level in the text format it's loaded from
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for y in 0..self.rows as i16 {
+            for x in 0..self.columns as i16 {
+                text.push(if (x, y) == self.start {
+                    start_marker(self.direction)
+                } else if self.is_wall(x, y) {
+                    '#'
+                } else if self.exit == Some((x, y)) {
+                    'E'
+                } else {
+                    '.'
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    // The level's name, which is empty for an open board
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn columns(&self) -> u16 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    // Where the cell at (x, y) is in `walls`. None if it's off the board.
+    fn index(&self, x: i16, y: i16) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.columns as i16 || y >= self.rows as i16 {
+            return None;
+        }
+        Some(y as usize * self.columns as usize + x as usize)
+    }
+
+    // Every wall, row by row
+    pub fn walls(&self) -> impl Iterator<Item = (i16, i16)> + '_ {
+        let columns = self.columns as usize;
+        (0..self.walls.len())
+            .filter(|&index| self.walls[index])
+            .map(move |index| ((index % columns) as i16, (index / columns) as i16))
+    }
+
+    pub fn is_wall(&self, x: i16, y: i16) -> bool {
+        self.index(x, y).is_some_and(|index| self.walls[index])
+    }
+
+    // Put up or take down a wall. A wall replaces the exit. Cells off the board are ignored.
+    pub fn set_wall(&mut self, x: i16, y: i16, wall: bool) {
+        let Some(index) = self.index(x, y) else {
+            return;
+        };
+        self.walls[index] = wall;
+        if wall && self.exit == Some((x, y)) {
+            self.exit = None;
+        }
+    }
+
+    pub fn exit(&self) -> Option<(i16, i16)> {
+        self.exit
+    }
+
+    // Move the exit, or take it away with None. The exit replaces a wall.
+    pub fn set_exit(&mut self, exit: Option<(i16, i16)>) {
+        if let Some((x, y)) = exit {
+            self.set_wall(x, y, false);
+        }
+        self.exit = exit;
+    }
+
+    pub fn target(&self) -> Option<u16> {
+        self.target
+    }
+
+    // The cells the snake starts on, from its head to its tail
+    pub fn snake_start(&self) -> impl Iterator<Item = (i16, i16)> {
+        let (x, y) = self.start;
+        let (dx, dy) = self.direction.offset();
+        (0..START_LENGTH).map(move |i| (x - dx * i, y - dy * i))
+    }
+
+    // The way the snake is heading at the start
+    pub fn start_direction(&self) -> Direction {
+        self.direction
+    }
+
+    // Move the start of the snake so its head is at (x, y) heading in `direction`
+    pub fn set_start(&mut self, x: i16, y: i16, direction: Direction) {
+        self.start = (x, y);
+        self.direction = direction;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_walls_and_pads_short_rows() {
+        let level = Level::parse("test", "; a comment\n....#\n.#\n\n\n").unwrap();
+        assert_eq!(level.name(), "test");
+        assert_eq!((level.columns(), level.rows()), (5, 2));
+        assert_eq!(level.walls().collect::<Vec<_>>(), vec![(4, 0), (1, 1)]);
+        assert!(level.is_wall(4, 0));
+        assert!(!level.is_wall(3, 0));
+        assert!(!level.is_wall(-1, 0));
+        assert!(!level.is_wall(5, 0));
+        assert_eq!(level.exit(), None);
+        assert_eq!(level.target(), None);
+    }
+
+    #[test]
+    fn spaces_and_crlf_are_empty_cells() {
+        let level = Level::parse("test", "....\r\n. #.\r\n").unwrap();
+        assert_eq!((level.columns(), level.rows()), (4, 2));
+        assert_eq!(level.walls().collect::<Vec<_>>(), vec![(2, 1)]);
+    }
+
+    #[test]
+    fn snake_starts_top_left_heading_east_without_a_marker() {
+        let level = Level::parse("test", "....\n....").unwrap();
+        assert_eq!(level.start_direction(), Direction::East);
+        assert_eq!(
+            level.snake_start().collect::<Vec<_>>(),
+            vec![(2, 0), (1, 0), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn start_markers_set_the_head_and_direction() {
+        let cases = [
+            (
+                "....\n....\n..^.\n....\n....",
+                Direction::North,
+                (2, 2),
+                (2, 4),
+            ),
+            ("....\n..>.\n....", Direction::East, (2, 1), (0, 1)),
+            ("....\n....\n.v..\n....", Direction::South, (1, 2), (1, 0)),
+            ("....\n.<..", Direction::West, (1, 1), (3, 1)),
+        ];
+        for (text, direction, head, tail) in cases {
+            let level = Level::parse("test", text).unwrap();
+            assert_eq!(level.start_direction(), direction);
+            let snake: Vec<_> = level.snake_start().collect();
+            assert_eq!(snake.first(), Some(&head));
+            assert_eq!(snake.last(), Some(&tail));
+        }
+    }
+
+    #[test]
+    fn parses_the_exit() {
+        let level = Level::parse("test", "..>.E\n.....").unwrap();
+        assert_eq!(level.exit(), Some((4, 0)));
+        assert!(!level.is_wall(4, 0));
+        assert_eq!(level.with_target(3).target(), Some(3));
+    }
+
+    #[test]
+    fn rejects_bad_levels() {
+        let cases = [
+            ("", "too small"),
+            ("...", "too small"),
+            ("....\n..x.", "'x' on row 2"),
+            ("..>.>.", "only start in one place"),
+            ("..>.\nE..E", "only be one exit"),
+            ("#.>.\n....", "empty cells"),
+            ("..v.\n....", "empty cells"),
+            (".#>.\n....", "empty cells"),
+            (">...\n....", "empty cells"),
+            ("E.>.\n....", "empty cells"),
+            ("##..\n....", "empty cells"),
+        ];
+        for (text, error) in cases {
+            let err = Level::parse("test", text).unwrap_err();
+            assert!(err.contains(error), "{:?} gave {:?}", text, err);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_to_text() {
+        let text = "......\n.#..#.\n..v.E.\n......\n..#...\n";
+        let level = Level::parse("test", text).unwrap();
+        assert_eq!(level.to_text(), text);
+        assert_eq!(Level::parse("test", &level.to_text()).unwrap(), level);
+    }
+
+    #[test]
+    fn built_in_levels_round_trip() {
+        for name in Level::built_in_names() {
+            let level = Level::by_name(name).unwrap();
+            assert_eq!(level.name(), name);
+            assert!(level.exit().is_some());
+            assert_eq!(Level::parse(name, &level.to_text()).unwrap(), level);
+        }
+        assert_eq!(Level::by_name("BOX").unwrap().name(), "box");
+        assert!(Level::by_name("nowhere").is_none());
+    }
+
+    #[test]
+    fn empty_level_writes_its_default_start() {
+        let level = Level::empty(5, 2);
+        assert_eq!(level.to_text(), "..>..\n.....\n");
+        assert_eq!(Level::parse("", &level.to_text()).unwrap(), level);
+    }
+
+    #[test]
+    fn walls_and_the_exit_replace_each_other() {
+        let mut level = Level::empty(6, 3);
+        level.set_exit(Some((4, 2)));
+        level.set_wall(4, 2, true);
+        assert!(level.is_wall(4, 2));
+        assert_eq!(level.exit(), None);
+        level.set_exit(Some((4, 2)));
+        assert!(!level.is_wall(4, 2));
+        assert_eq!(level.exit(), Some((4, 2)));
+        level.set_wall(4, 2, false);
+        assert_eq!(level.exit(), Some((4, 2)));
+        // Off the board is ignored
+        level.set_wall(6, 0, true);
+        assert_eq!(level.walls().count(), 0);
+    }
+
+    #[test]
+    fn checks_board_sizes() {
+        assert!(Level::check_size(4, 1).is_ok());
+        assert!(Level::check_size(3, 10).is_err());
+        assert!(Level::check_size(10, 0).is_err());
+        assert!(Level::check_size(i16::MAX as u16, 32).is_ok());
+        assert!(Level::check_size(32, i16::MAX as u16).is_ok());
+        assert!(Level::check_size(1024, 1024).is_ok());
+        assert!(Level::check_size(1024, 1025).is_err());
+        assert!(Level::check_size(i16::MAX as u16, i16::MAX as u16).is_err());
+        assert!(Level::check_size(i16::MAX as u16 + 1, 1).is_err());
+        assert!(Level::check_size(4, i16::MAX as u16 + 1).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright (c) 2022 DanWillans
+// RustySnake as a library. The game rules live in `game` and know nothing about the terminal,
+// `rendering` and `view` draw them with crossterm. Bots, servers and test harnesses can drive a
+// GameBoard with `GameBoard::step` and never touch a DrawScreen.
+// The screens the game moves between, from the title to the menus, are Scenes run by a SceneStack
+// in `scene`.
+pub mod campaign;
+pub mod config;
+pub mod editor;
+pub mod game;
+pub mod grid;
+pub mod input;
+pub mod level;
+pub mod menu;
+pub mod palette;
+pub mod pause;
+pub mod play;
+pub mod rendering;
+pub mod replay;
+pub mod scene;
+pub mod scores;
+pub mod speed;
+pub mod theme;
+pub mod title;
+pub mod view;
+pub mod watch;
+
+pub use config::Config;
+pub use game::{Direction, GameBoard, GameEvent, SnakeElement};
+pub use input::{Command, InputEvent, KeyBindings};
+pub use level::Level;
+pub use menu::{Menu, MenuInput};
+pub use palette::{ColorSupport, Palette, PaletteColor};
+pub use rendering::{DrawColor, DrawScreen, ScreenBuffer};
+pub use replay::{Replay, ReplayPlayer};
+pub use scene::{Context, Scene, SceneStack, Transition};
+pub use scores::{HighScores, ScoreEntry};
+pub use speed::SpeedCurve;
+pub use theme::GlyphTheme;
+pub use view::{draw_title_screen, BoardView};
@@ -1,134 +1,374 @@
 // Copyright (c) 2022 DanWillans
-mod game;
-mod rendering;
+use clap::{Args, Parser, Subcommand};
 use crossterm::Result;
-use device_query::{DeviceQuery, DeviceState, Keycode};
-use game::{draw_title_screen, Direction, GameBoard};
-use rendering::{DrawColor, DrawScreen};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc;
+use rusty_snake::campaign::{Campaign, CampaignScene};
+use rusty_snake::editor::EditorScene;
+use rusty_snake::input::spawn_input_thread;
+use rusty_snake::scores::format_duration;
+use rusty_snake::title::TitleScene;
+use rusty_snake::watch::WatchScene;
+use rusty_snake::{
+    Config, Context, Direction, DrawScreen, GameBoard, GlyphTheme, HighScores, Level, Palette,
+    Replay, Scene, SceneStack,
+};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
-use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
-fn main() -> Result<()> {
-    // Game constants
-    let screen_width = 150;
-    let screen_height = 40;
-    let game_board_width = 80;
-    let game_board_height = screen_height - 2;
-    let game_board_start_position = (screen_width / 2 - game_board_width / 2, 1);
+// Command line interface. Running with no subcommand plays the game.
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(flatten)]
+    play: PlayArgs,
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
 
-    // Setup the DrawScreen which will be used by other components
-    let mut draw_screen = DrawScreen::new(screen_width, screen_height);
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Play the game (the default)
+    Play(PlayArgs),
+    /// Print the high-score table
+    Scores,
+    /// Watch a recorded game
+    Replay(ReplayArgs),
+    /// Make or change a level file
+    Edit(EditArgs),
+    /// Play through a campaign of levels, opening each level's exit by eating enough food
+    Campaign(CampaignArgs),
+    /// Play games with a simple autopilot and no terminal output, then print how they went
+    Bench(BenchArgs),
+}
 
-    // Draw title screen
-    draw_title_screen((35, 17), &mut draw_screen);
-    draw_screen.draw();
+// Overrides for the settings in the config file
+#[derive(Args, Default)]
+struct PlayArgs {
+    /// Read settings from this file instead of the default config.toml
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Board columns. Both sizes left out fills the terminal
+    #[arg(long)]
+    width: Option<u16>,
+    /// Board rows
+    #[arg(long)]
+    height: Option<u16>,
+    /// Level to play: box, pillars, cross or the path to a level file. Sets the board size
+    #[arg(long)]
+    level: Option<String>,
+    /// Go through the edges of the board and come back in at the opposite side
+    #[arg(long)]
+    wrap: bool,
+    /// Moves per second
+    #[arg(long)]
+    speed: Option<f64>,
+    /// Use the same seed for every game so it can be played again
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Glyph theme: emoji, unicode or ascii
+    #[arg(long, value_parser = parse_theme)]
+    theme: Option<GlyphTheme>,
+    /// Colour palette: default, light, colorblind, mono or the path to a theme file
+    #[arg(long, value_parser = Palette::by_name_or_file)]
+    palette: Option<Palette>,
+}
 
-    // Setup input from keyboard handling
-    let (tx, rx) = mpsc::channel();
-    thread::spawn(move || {
-        let device_state = DeviceState::new();
-        let mut last_key = &Keycode::Escape;
-        loop {
-            let keys: Vec<Keycode> = device_state.get_keys();
-            if let Some(key) = keys.last() {
-                if key == &Keycode::W && last_key != &Keycode::W {
-                    tx.send(Keycode::W).unwrap();
-                    last_key = &Keycode::W;
-                } else if key == &Keycode::D && last_key != &Keycode::D {
-                    tx.send(Keycode::D).unwrap();
-                    last_key = &Keycode::D;
-                } else if key == &Keycode::S && last_key != &Keycode::S {
-                    tx.send(Keycode::S).unwrap();
-                    last_key = &Keycode::S;
-                } else if key == &Keycode::A && last_key != &Keycode::A {
-                    tx.send(Keycode::A).unwrap();
-                    last_key = &Keycode::A;
-                } else if key == &Keycode::Enter && last_key != &Keycode::Enter {
-                    tx.send(Keycode::Enter).unwrap();
-                    last_key = &Keycode::Enter;
-                } else if key == &Keycode::Space && last_key != &Keycode::Space {
-                    tx.send(Keycode::Space).unwrap();
-                    last_key = &Keycode::Space;
-                }
-            }
-            thread::sleep(Duration::from_millis(1));
-        }
-    });
-
-    // Wait for space bar to be pressed
-    loop {
-        if let Ok(key) = rx.try_recv() {
-            if key == Keycode::Space {
-                break;
-            }
+#[derive(Args)]
+struct ReplayArgs {
+    /// A replay file. Every game is saved to the replays directory next to the high scores
+    file: PathBuf,
+    /// Play the replay without drawing it and check it ends with the recorded score
+    #[arg(long)]
+    verify: bool,
+}
+
+#[derive(Args)]
+struct EditArgs {
+    /// The level file. It's created when it's saved if it doesn't exist yet
+    file: PathBuf,
+    /// Columns of a new level
+    #[arg(long, default_value_t = 24)]
+    width: u16,
+    /// Rows of a new level
+    #[arg(long, default_value_t = 14)]
+    height: u16,
+}
+
+#[derive(Args)]
+struct CampaignArgs {
+    /// A campaign file listing the levels to play. Leave it out for the campaign that comes with
+    /// the game
+    file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// Number of games to play
+    #[arg(long, default_value_t = 100)]
+    games: u32,
+    /// Board columns
+    #[arg(long, default_value_t = 40)]
+    width: u16,
+    /// Board rows
+    #[arg(long, default_value_t = 20)]
+    height: u16,
+    /// Seed of the first game. Each game after it adds one
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Play on boards that wrap around at the edges
+    #[arg(long)]
+    wrap: bool,
+}
+
+fn parse_theme(name: &str) -> std::result::Result<GlyphTheme, String> {
+    GlyphTheme::by_name(name).ok_or(format!("there's no '{}' theme", name))
+}
+
+// Load the config file and apply the command line arguments on top of it
+fn load_config(args: PlayArgs) -> std::result::Result<Config, String> {
+    // A config file given on the command line has to exist, the default one doesn't
+    let mut config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::load_default()?,
+    };
+    if let Some(speed) = args.speed {
+        config.set_speed(speed)?;
+    }
+    // Either size given on its own is taken with the other from the config file
+    if args.width.is_some() || args.height.is_some() {
+        let (width, height) = config.board_size.unzip();
+        match (args.width.or(width), args.height.or(height)) {
+            (Some(width), Some(height)) => config.set_board_size(width, height)?,
+            _ => return Err("--width and --height have to be given together".to_string()),
         }
-        thread::sleep(Duration::from_millis(1));
     }
+    if let Some(level) = &args.level {
+        config.level = Some(Level::by_name_or_file(level)?);
+    }
+    config.wrap |= args.wrap;
+    config.seed = args.seed.or(config.seed);
+    config.theme = args.theme.unwrap_or(config.theme);
+    config.palette = args.palette.unwrap_or(config.palette);
+    Ok(config)
+}
+
+// Pick a direction that doesn't crash straight away, heading for the food when it can.
+// It doesn't look ahead so it will eventually trap itself.
+fn autopilot(board: &GameBoard) -> Direction {
+    let current = board.snake_direction();
+    let Some(head) = board.snake_head() else {
+        return current;
+    };
+    let (food_x, food_y) = board.food_position();
+    [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ]
+    .into_iter()
+    .filter(|direction| *direction != current.opposite())
+    .map(|direction| {
+        let (x, y) = board.next_position(head.x, head.y, direction);
+        let distance = (food_x - x).abs() + (food_y - y).abs();
+        (
+            !board.is_free(x, y),
+            distance,
+            direction != current,
+            direction,
+        )
+    })
+    .min_by_key(|(blocked, distance, turned, _)| (*blocked, *distance, *turned))
+    .map_or(current, |(_, _, _, direction)| direction)
+}
 
-    draw_screen.clear();
+// Run games headless as fast as possible and print the results
+fn bench(args: BenchArgs) -> std::result::Result<(), String> {
+    if args.games == 0 {
+        return Err("--games has to be at least 1".to_string());
+    }
+    Level::check_size(args.width, args.height)?;
+    let level = Level::empty(args.width, args.height);
+    let first_seed = args.seed.unwrap_or_else(rand::random);
+    let mut steps: u64 = 0;
+    let mut total_score: u64 = 0;
+    let mut best_score = 0;
+    let mut wins = 0;
+    let start = Instant::now();
+    for game in 0..args.games {
+        let seed = first_seed.wrapping_add(game as u64);
+        let mut board = GameBoard::with_seed(&level, seed).with_wrap(args.wrap);
+        while board.is_active() {
+            board.step(Some(autopilot(&board)));
+            steps += 1;
+        }
+        total_score += board.score() as u64;
+        best_score = best_score.max(board.score());
+        if board.has_won() {
+            wins += 1;
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "{} games on a {}x{} {} board starting at seed {}",
+        args.games,
+        args.width,
+        args.height,
+        if args.wrap { "wrap" } else { "classic" },
+        first_seed
+    );
+    println!(
+        "Average score {:.1}, best {}, {} won",
+        total_score as f64 / args.games as f64,
+        best_score,
+        wins
+    );
+    println!(
+        "{} steps in {:.3}s, {:.0} steps per second",
+        steps,
+        elapsed,
+        steps as f64 / elapsed.max(f64::EPSILON)
+    );
+    Ok(())
+}
 
-    // Setup the GameBoard
-    let mut game_board = GameBoard::new(
-        game_board_start_position,
-        game_board_width,
-        game_board_height,
-        &mut draw_screen,
+// Print the high-score table to stdout
+fn print_scores() -> std::result::Result<(), String> {
+    let path = HighScores::default_path().ok_or("Couldn't find the data directory")?;
+    let scores = HighScores::load(&path)?;
+    if scores.entries().is_empty() {
+        println!("No high scores yet");
+        return Ok(());
+    }
+    println!(
+        "{:>2}  {:<12} {:>5} {:>6} {:>6}  {:<20} {:<14} Date",
+        "#", "Name", "Score", "Length", "Time", "Seed", "Mode"
     );
+    for (index, entry) in scores.entries().iter().enumerate() {
+        println!(
+            "{:>2}  {:<12} {:>5} {:>6} {:>6}  {:<20} {:<14} {}",
+            index + 1,
+            entry.name,
+            entry.score,
+            entry.length,
+            format_duration(entry.duration),
+            entry.seed,
+            entry.mode,
+            entry.date
+        );
+    }
+    Ok(())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        None => play(cli.play),
+        Some(CliCommand::Play(args)) => play(args),
+        Some(CliCommand::Scores) => print_scores(),
+        Some(CliCommand::Replay(args)) => replay(args),
+        Some(CliCommand::Edit(args)) => edit(args),
+        Some(CliCommand::Campaign(args)) => campaign(args),
+        Some(CliCommand::Bench(args)) => bench(args),
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(2);
+    }
+}
 
-    // Setup required parameters for FPS
-    let desired_fps: f64 = 10.0;
-    let time_per_frame: f64 = 1.0 / desired_fps;
-    let mut last_time = Instant::now();
+fn play(args: PlayArgs) -> std::result::Result<(), String> {
+    // Settings from the config file and command line
+    let config = load_config(args)?;
+    run_scenes(config, Box::new(TitleScene::new()), "title", None).map_err(|err| err.to_string())
+}
 
-    // Setup signal handler to catch ctrl+c
+// Setup signal handler to catch SIGINT and SIGTERM. Loops stop when the returned flag is set which
+// drops the DrawScreen and restores the terminal.
+fn capture_signals() -> Result<Arc<AtomicBool>> {
     let signal_capture = Arc::new(AtomicBool::new(false));
     signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&signal_capture))?;
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&signal_capture))?;
+    Ok(signal_capture)
+}
 
-    // Loop until a signal has been captured
-    while !signal_capture.load(Ordering::Relaxed) {
-        // Check if input has been sent from the input thread. If it has then update the snake direction
-        if let Ok(key) = rx.try_recv() {
-            if key == Keycode::W {
-                game_board.update_snake_direction(Direction::North);
-            } else if key == Keycode::D {
-                game_board.update_snake_direction(Direction::East);
-            } else if key == Keycode::S {
-                game_board.update_snake_direction(Direction::South);
-            } else if key == Keycode::A {
-                game_board.update_snake_direction(Direction::West);
-            } else if key == Keycode::Enter {
-                draw_screen.draw_border();
-                game_board = game_board.reset(&mut draw_screen);
-            }
-        }
+// Setup the DrawScreen which will be used by other components. It covers the whole terminal and
+// puts it into raw mode so key presses arrive straight away instead of waiting for the Enter key.
+fn open_screen(config: &Config) -> DrawScreen {
+    let mut draw_screen = DrawScreen::fullscreen();
+    draw_screen.set_ascii_borders(config.theme.ascii_borders);
+    draw_screen.set_palette(&config.palette);
+    draw_screen
+}
 
-        // Update the GameBoard. This moves and grows the snake, detects collisions and respawns food.
-        game_board.update(&mut draw_screen);
+fn replay(args: ReplayArgs) -> std::result::Result<(), String> {
+    let replay = Replay::load(&args.file)?;
+    if args.verify {
+        let board = replay
+            .verify()
+            .map_err(|err| format!("{}: {}", args.file.display(), err))?;
+        println!(
+            "{}: score {} after {} steps as recorded",
+            args.file.display(),
+            board.score(),
+            board.steps()
+        );
+        return Ok(());
+    }
+    let config = Config::load_default()?;
+    run_scenes(config, Box::new(WatchScene::new(replay)), "replay", None)
+        .map_err(|err| err.to_string())
+}
 
-        // Flush the queued changes to draw the screen
-        draw_screen.draw();
+fn edit(args: EditArgs) -> std::result::Result<(), String> {
+    let (level, unsaved) = if args.file.exists() {
+        (Level::load(&args.file)?, false)
+    } else {
+        Level::check_size(args.width, args.height)?;
+        let name = args.file.file_stem().unwrap_or_default().to_string_lossy();
+        let level = Level::empty(args.width, args.height).with_name(&name);
+        level.check()?;
+        (level, true)
+    };
+    let config = Config::load_default()?;
+    let editor = EditorScene::new(level, args.file, unsaved);
+    run_scenes(config, Box::new(editor), "editor", None).map_err(|err| err.to_string())
+}
 
-        // Wait a specified amount of time to reach the desired FPS
-        let wait_time = time_per_frame - last_time.elapsed().as_secs_f64();
-        if wait_time > 0.0 {
-            thread::sleep(Duration::from_secs_f64(wait_time));
-        }
-        // Calculate the actual FPS based on elapsed time after the wait and draw queue the value to be drawn to the screen.
-        let elapsed_time = last_time.elapsed();
-        let fps = 1.0 / elapsed_time.as_secs_f32();
-        let fps_text = format!("fps: {:.2}", fps);
-        draw_screen.update_with_string(
-            screen_width - (fps_text.len() + 1) as u16,
-            1,
-            fps_text,
-            DrawColor::White,
-        );
+fn campaign(args: CampaignArgs) -> std::result::Result<(), String> {
+    let campaign = match &args.file {
+        Some(file) => Campaign::load(file)?,
+        None => Campaign::built_in(),
+    };
+    let config = Config::load_default()?;
+    run_scenes(
+        config,
+        Box::new(CampaignScene::new()),
+        "campaign",
+        Some(campaign),
+    )
+    .map_err(|err| err.to_string())
+}
 
-        // Update time
-        last_time = Instant::now();
-    }
+// Open the terminal and run scenes starting with `first` until the player quits. `home` names the
+// first scene in the menus that go back to it and `campaign` is the levels being played, if any.
+fn run_scenes(
+    config: Config,
+    first: Box<dyn Scene>,
+    home: &'static str,
+    campaign: Option<Campaign>,
+) -> Result<()> {
+    let signal_capture = capture_signals()?;
+    let draw_screen = open_screen(&config);
+
+    // Setup input from keyboard handling
+    let rx = spawn_input_thread();
+
+    // Everything from the title screen to the menus is a Scene. Only the scene on top of the stack
+    // gets input and ticks, and the game ends when the last one closes.
+    let mut context = Context::new(config, draw_screen, home, campaign);
+    let mut scenes = SceneStack::new(first, &mut context);
+    scenes.run(&mut context, &rx, &signal_capture);
     Ok(())
 }
@@ -0,0 +1,114 @@
+// Copyright (c) 2022 DanWillans
+use crate::{Command, Direction};
+use crossterm::event::{KeyCode, KeyEvent};
+
+// What a key press did to a Menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuInput<T> {
+    // The selection moved to another item
+    Moved,
+    // The item was picked with Enter or the space bar
+    Chosen(T),
+    // Left or right was pressed on the item. -1 is left and 1 is right.
+    Adjusted(T, i8),
+    // The menu should close, like Esc
+    Back,
+    // The key doesn't do anything in a menu
+    Ignored,
+}
+
+// A list of items with one of them selected. Each item has a label to show and a value that's
+// handed back when it's chosen. The menu only keeps track of the selection, drawing it is up to
+// draw_menu in view.rs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Menu<T> {
+    title: String,
+    items: Vec<(String, T)>,
+    selected: usize,
+}
+
+impl<T: Copy + PartialEq> Menu<T> {
+    pub fn new(title: &str, items: Vec<(String, T)>) -> Self {
+        Menu {
+            title: title.to_string(),
+            items,
+            selected: 0,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    // Labels of every item from top to bottom
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.items.iter().map(|(label, _)| label.as_str())
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected(&self) -> Option<T> {
+        self.items.get(self.selected).map(|(_, value)| *value)
+    }
+
+    // Select the item with `value` if there is one
+    pub fn select(&mut self, value: T) {
+        if let Some(index) = self.items.iter().position(|(_, item)| *item == value) {
+            self.selected = index;
+        }
+    }
+
+    // Change the label of the item with `value`, for items that show a setting
+    pub fn set_label(&mut self, value: T, label: String) {
+        if let Some(item) = self.items.iter_mut().find(|(_, item)| *item == value) {
+            item.0 = label;
+        }
+    }
+
+    // Move the selection up or down, wrapping around at the ends
+    fn move_selection(&mut self, down: bool) {
+        let count = self.items.len().max(1);
+        self.selected = if down {
+            (self.selected + 1) % count
+        } else {
+            (self.selected + count - 1) % count
+        };
+    }
+
+    // Handle a key press. The arrow keys always work as well as the keys bound to turning, and
+    // `command` is what the key is bound to.
+    pub fn handle_key(&mut self, key: &KeyEvent, command: Option<Command>) -> MenuInput<T> {
+        let direction = match (key.code, command) {
+            (KeyCode::Up, _) | (_, Some(Command::Turn(Direction::North))) => Some(Direction::North),
+            (KeyCode::Down, _) | (_, Some(Command::Turn(Direction::South))) => {
+                Some(Direction::South)
+            }
+            (KeyCode::Left, _) | (_, Some(Command::Turn(Direction::West))) => Some(Direction::West),
+            (KeyCode::Right, _) | (_, Some(Command::Turn(Direction::East))) => {
+                Some(Direction::East)
+            }
+            _ => None,
+        };
+        let Some(selected) = self.selected() else {
+            return MenuInput::Back;
+        };
+        match (key.code, direction, command) {
+            (_, Some(Direction::North), _) => {
+                self.move_selection(false);
+                MenuInput::Moved
+            }
+            (_, Some(Direction::South), _) => {
+                self.move_selection(true);
+                MenuInput::Moved
+            }
+            (_, Some(Direction::West), _) => MenuInput::Adjusted(selected, -1),
+            (_, Some(Direction::East), _) => MenuInput::Adjusted(selected, 1),
+            (KeyCode::Enter | KeyCode::Char(' '), _, _)
+            | (_, _, Some(Command::Start | Command::Restart)) => MenuInput::Chosen(selected),
+            (KeyCode::Esc, _, _) | (_, _, Some(Command::Pause)) => MenuInput::Back,
+            _ => MenuInput::Ignored,
+        }
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright (c) 2022 DanWillans
+use crate::DrawColor;
+use crossterm::style::Color;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// The standard 16 terminal colours in index order along with the RGB values xterm uses for them
+const ANSI16: [(&str, Color, (u8, u8, u8)); 16] = [
+    ("black", Color::Black, (0, 0, 0)),
+    ("dark_red", Color::DarkRed, (205, 0, 0)),
+    ("dark_green", Color::DarkGreen, (0, 205, 0)),
+    ("dark_yellow", Color::DarkYellow, (205, 205, 0)),
+    ("dark_blue", Color::DarkBlue, (0, 0, 238)),
+    ("dark_magenta", Color::DarkMagenta, (205, 0, 205)),
+    ("dark_cyan", Color::DarkCyan, (0, 205, 205)),
+    ("grey", Color::Grey, (229, 229, 229)),
+    ("dark_grey", Color::DarkGrey, (127, 127, 127)),
+    ("red", Color::Red, (255, 0, 0)),
+    ("green", Color::Green, (0, 255, 0)),
+    ("yellow", Color::Yellow, (255, 255, 0)),
+    ("blue", Color::Blue, (92, 92, 255)),
+    ("magenta", Color::Magenta, (255, 0, 255)),
+    ("cyan", Color::Cyan, (0, 255, 255)),
+    ("white", Color::White, (255, 255, 255)),
+];
+
+// Levels used by each channel of the 6x6x6 colour cube in the 256 colour palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// How many colours the terminal can show
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    // No colour at all, either because NO_COLOR is set or the terminal is dumb
+    Monochrome,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorSupport {
+    // Work out what the terminal supports from the environment.
+    // NO_COLOR (https://no-color.org) always wins.
+    pub fn detect() -> ColorSupport {
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return ColorSupport::Monochrome;
+        }
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorSupport::TrueColor;
+        }
+        let term = env::var("TERM").unwrap_or_default();
+        if term == "dumb" {
+            ColorSupport::Monochrome
+        } else if term.contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+}
+
+// A colour in one of the forms a terminal understands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteColor {
+    Rgb(u8, u8, u8),
+    // Index into the 256 colour palette
+    Ansi256(u8),
+    // Index into the standard 16 colours
+    Ansi16(u8),
+    // Whatever colour the terminal draws text in when it's not told otherwise
+    Default,
+}
+
+impl PaletteColor {
+    // Parse "#rrggbb", a 256 colour index like "208", one of the 16 colour names like
+    // "dark_yellow" or "default" for the terminal's own text colour
+    pub fn parse(text: &str) -> Result<PaletteColor, String> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("default") {
+            return Ok(PaletteColor::Default);
+        }
+        if let Some(hex) = text.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(PaletteColor::Rgb(r, g, b)),
+                _ => Err(format!("'{}' isn't a #rrggbb colour", text)),
+            };
+        }
+        if let Ok(index) = text.parse::<u8>() {
+            return Ok(PaletteColor::Ansi256(index));
+        }
+        ANSI16
+            .iter()
+            .position(|(name, _, _)| name.eq_ignore_ascii_case(text))
+            .map(|index| PaletteColor::Ansi16(index as u8))
+            .ok_or_else(|| format!("'{}' isn't a colour", text))
+    }
+
+    fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            PaletteColor::Rgb(r, g, b) => (r, g, b),
+            PaletteColor::Ansi16(index) => ANSI16[index as usize % 16].2,
+            PaletteColor::Ansi256(index) if index < 16 => ANSI16[index as usize].2,
+            PaletteColor::Ansi256(index) if index < 232 => {
+                let cube = index - 16;
+                (
+                    CUBE_LEVELS[(cube / 36) as usize],
+                    CUBE_LEVELS[(cube / 6 % 6) as usize],
+                    CUBE_LEVELS[(cube % 6) as usize],
+                )
+            }
+            PaletteColor::Ansi256(index) => {
+                let grey = 8 + (index - 232) * 10;
+                (grey, grey, grey)
+            }
+            // Near enough to the usual text colour on a dark background
+            PaletteColor::Default => ANSI16[7].2,
+        }
+    }
+
+    // Convert to the closest colour the terminal can show
+    pub fn downgrade(self, support: ColorSupport) -> PaletteColor {
+        match (self, support) {
+            (PaletteColor::Rgb(r, g, b), ColorSupport::Ansi256) => {
+                PaletteColor::Ansi256(nearest_ansi256((r, g, b)))
+            }
+            (PaletteColor::Rgb(..), ColorSupport::Ansi16 | ColorSupport::Monochrome)
+            | (PaletteColor::Ansi256(16..), ColorSupport::Ansi16 | ColorSupport::Monochrome) => {
+                PaletteColor::Ansi16(nearest_ansi16(self.to_rgb()))
+            }
+            (PaletteColor::Ansi256(index), ColorSupport::Ansi16 | ColorSupport::Monochrome) => {
+                PaletteColor::Ansi16(index)
+            }
+            _ => self,
+        }
+    }
+
+    pub fn to_crossterm(self) -> Color {
+        match self {
+            PaletteColor::Rgb(r, g, b) => Color::Rgb { r, g, b },
+            PaletteColor::Ansi256(index) => Color::AnsiValue(index),
+            PaletteColor::Ansi16(index) => ANSI16[index as usize % 16].1,
+            PaletteColor::Default => Color::Reset,
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn nearest_ansi16(rgb: (u8, u8, u8)) -> u8 {
+    (0..16u8)
+        .min_by_key(|&index| distance(rgb, ANSI16[index as usize].2))
+        .unwrap_or(15)
+}
+
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    // Only look at the cube and the grey ramp. The first 16 colours vary between terminals.
+    (16..=255u8)
+        .min_by_key(|&index| distance(rgb, PaletteColor::Ansi256(index).to_rgb()))
+        .unwrap_or(15)
+}
+
+// The colour used for each DrawColor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    // Text like the score and messages
+    pub white: PaletteColor,
+    // The snake and winning messages
+    pub green: PaletteColor,
+    // The food and losing messages
+    pub red: PaletteColor,
+    // The border around the screen
+    pub border: PaletteColor,
+    // The border around the board and the title
+    pub game_border: PaletteColor,
+}
+
+impl Palette {
+    // The original colours, made for dark backgrounds
+    pub const DEFAULT: Palette = Palette {
+        white: PaletteColor::Ansi16(15),
+        green: PaletteColor::Ansi16(10),
+        red: PaletteColor::Ansi16(9),
+        border: PaletteColor::Ansi16(10),
+        game_border: PaletteColor::Ansi16(3),
+    };
+
+    // Darker colours that stay readable on light backgrounds
+    pub const LIGHT: Palette = Palette {
+        white: PaletteColor::Ansi16(0),
+        green: PaletteColor::Rgb(0, 135, 0),
+        red: PaletteColor::Rgb(175, 0, 0),
+        border: PaletteColor::Rgb(0, 95, 135),
+        game_border: PaletteColor::Rgb(135, 95, 0),
+    };
+
+    // Blue and orange from the Okabe-Ito palette so the snake and food are easy to tell apart with
+    // the common forms of colour blindness
+    pub const COLORBLIND: Palette = Palette {
+        white: PaletteColor::Ansi16(15),
+        green: PaletteColor::Rgb(86, 180, 233),
+        red: PaletteColor::Rgb(230, 159, 0),
+        border: PaletteColor::Rgb(0, 114, 178),
+        game_border: PaletteColor::Rgb(240, 228, 66),
+    };
+
+    // The terminal's own text colour for everything, for when colours get in the way
+    pub const MONO: Palette = Palette {
+        white: PaletteColor::Default,
+        green: PaletteColor::Default,
+        red: PaletteColor::Default,
+        border: PaletteColor::Default,
+        game_border: PaletteColor::Default,
+    };
+
+    // Look up a built in palette by name
+    pub fn by_name(name: &str) -> Option<Palette> {
+        match name.to_ascii_lowercase().as_str() {
+            "default" => Some(Palette::DEFAULT),
+            "light" => Some(Palette::LIGHT),
+            "colorblind" => Some(Palette::COLORBLIND),
+            "mono" => Some(Palette::MONO),
+            _ => None,
+        }
+    }
+
+    // Load a palette from a theme file. Each line sets a colour like `red = "#e69f00"` and any
+    // colour that isn't mentioned keeps its default. Comments start with `#`.
+    pub fn load(path: &Path) -> Result<Palette, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        Palette::parse(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    // A built in palette if there's one called `value`, otherwise the theme file at that path
+    pub fn by_name_or_file(value: &str) -> Result<Palette, String> {
+        match Palette::by_name(value) {
+            Some(palette) => Ok(palette),
+            None => Palette::load(Path::new(value)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Palette, String> {
+        let table = text.parse::<toml::Value>().map_err(|err| err.to_string())?;
+        let table = table.as_table().ok_or("expected a table of colours")?;
+        let mut palette = Palette::DEFAULT;
+        for (key, value) in table {
+            let color = match value {
+                toml::Value::String(text) => PaletteColor::parse(text)?,
+                toml::Value::Integer(index) => u8::try_from(*index)
+                    .map(PaletteColor::Ansi256)
+                    .map_err(|_| format!("{} isn't a 256 colour index", index))?,
+                _ => return Err(format!("'{}' should be a colour", key)),
+            };
+            match key.as_str() {
+                "white" => palette.white = color,
+                "green" => palette.green = color,
+                "red" => palette.red = color,
+                "border" => palette.border = color,
+                "game_border" => palette.game_border = color,
+                _ => return Err(format!("unknown colour '{}'", key)),
+            }
+        }
+        Ok(palette)
+    }
+
+    // A copy of the palette using only colours the terminal can show
+    pub fn downgrade(&self, support: ColorSupport) -> Palette {
+        Palette {
+            white: self.white.downgrade(support),
+            green: self.green.downgrade(support),
+            red: self.red.downgrade(support),
+            border: self.border.downgrade(support),
+            game_border: self.game_border.downgrade(support),
+        }
+    }
+
+    pub fn get(&self, color: DrawColor) -> PaletteColor {
+        match color {
+            DrawColor::White => self.white,
+            DrawColor::Green => self.green,
+            DrawColor::Red => self.red,
+            DrawColor::Border => self.border,
+            DrawColor::GameBorder => self.game_border,
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_index_and_named_colours() {
+        assert_eq!(
+            PaletteColor::parse("#ff8000"),
+            Ok(PaletteColor::Rgb(255, 128, 0))
+        );
+        assert_eq!(
+            PaletteColor::parse(" #0072B2 "),
+            Ok(PaletteColor::Rgb(0, 114, 178))
+        );
+        assert_eq!(PaletteColor::parse("208"), Ok(PaletteColor::Ansi256(208)));
+        assert_eq!(
+            PaletteColor::parse("Dark_Yellow"),
+            Ok(PaletteColor::Ansi16(3))
+        );
+        assert_eq!(PaletteColor::parse("default"), Ok(PaletteColor::Default));
+    }
+
+    #[test]
+    fn only_six_digit_hex_colours_are_accepted() {
+        for text in ["#fff", "#ff800", "#ff80001", "#gg0000", "#", "ff8000"] {
+            assert!(PaletteColor::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn indexes_have_to_fit_the_256_colour_palette() {
+        assert_eq!(PaletteColor::parse("255"), Ok(PaletteColor::Ansi256(255)));
+        assert!(PaletteColor::parse("256").is_err());
+        assert!(PaletteColor::parse("-1").is_err());
+    }
+
+    #[test]
+    fn downgrades_truecolor_to_256_to_16_colours() {
+        let orange = PaletteColor::Rgb(255, 135, 0);
+        assert_eq!(orange.downgrade(ColorSupport::TrueColor), orange);
+        assert_eq!(
+            orange.downgrade(ColorSupport::Ansi256),
+            PaletteColor::Ansi256(208)
+        );
+        assert_eq!(
+            orange.downgrade(ColorSupport::Ansi16),
+            PaletteColor::Ansi16(3)
+        );
+        assert_eq!(
+            PaletteColor::Ansi256(208).downgrade(ColorSupport::Ansi16),
+            PaletteColor::Ansi16(3)
+        );
+        // The first 16 of the 256 colours are the 16 colours
+        assert_eq!(
+            PaletteColor::Ansi256(3).downgrade(ColorSupport::Ansi16),
+            PaletteColor::Ansi16(3)
+        );
+        for support in [ColorSupport::Monochrome, ColorSupport::Ansi16] {
+            assert_eq!(
+                PaletteColor::Ansi16(10).downgrade(support),
+                PaletteColor::Ansi16(10)
+            );
+            assert_eq!(
+                PaletteColor::Default.downgrade(support),
+                PaletteColor::Default
+            );
+        }
+    }
+
+    #[test]
+    fn nearest_256_colour_uses_the_cube_and_the_grey_ramp() {
+        assert_eq!(nearest_ansi256((0, 0, 0)), 16);
+        assert_eq!(nearest_ansi256((255, 255, 255)), 231);
+        assert_eq!(nearest_ansi256((95, 135, 175)), 67);
+        assert_eq!(nearest_ansi256((250, 130, 5)), 208);
+        // Greys between the cube's levels are closer to the ramp
+        assert_eq!(nearest_ansi256((8, 8, 8)), 232);
+        assert_eq!(nearest_ansi256((128, 128, 128)), 244);
+        assert_eq!(nearest_ansi256((238, 238, 238)), 255);
+    }
+
+    #[test]
+    fn palette_files_change_only_the_colours_they_mention() {
+        let palette =
+            Palette::parse("# my colours\nred = \"#e69f00\"\ngreen = 33\nborder = \"blue\"\n")
+                .unwrap();
+        assert_eq!(
+            palette,
+            Palette {
+                red: PaletteColor::Rgb(230, 159, 0),
+                green: PaletteColor::Ansi256(33),
+                border: PaletteColor::Ansi16(12),
+                ..Palette::DEFAULT
+            }
+        );
+        assert_eq!(Palette::parse(""), Ok(Palette::DEFAULT));
+        for text in [
+            "blue = 1",
+            "red = 256",
+            "red = -1",
+            "red = true",
+            "red = \"#fff\"",
+        ] {
+            assert!(Palette::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn mono_palette_uses_the_terminal_colour() {
+        assert_eq!(Palette::by_name("Mono"), Some(Palette::MONO));
+        assert_eq!(Palette::MONO.downgrade(ColorSupport::Ansi16), Palette::MONO);
+        assert_eq!(
+            Palette::MONO.get(DrawColor::Red).to_crossterm(),
+            Color::Reset
+        );
+    }
+}
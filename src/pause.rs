@@ -0,0 +1,273 @@
+// Copyright (c) 2022 DanWillans
+use crate::play::PlayingScene;
+use crate::scene::{Context, Scene, Transition};
+use crate::view::draw_menu;
+use crate::{Command, Config, DrawScreen, InputEvent, Menu, MenuInput, Palette};
+use crossterm::event::KeyCode;
+
+// The menus here are drawn over a game. Each one knows the screen size the game needs so it can
+// stay out of the way of the message asking for a bigger terminal, and so the game only carries on
+// once it fits again.
+fn fits(needed: (u16, u16), screen: &DrawScreen) -> bool {
+    screen.width() >= needed.0 && screen.height() >= needed.1
+}
+
+// The items in the pause menu
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PauseChoice {
+    Resume,
+    Restart,
+    Settings,
+    QuitToHome,
+    Quit,
+}
+
+// The menu shown while the game is paused
+pub struct PauseScene {
+    menu: Menu<PauseChoice>,
+    needed: (u16, u16),
+}
+
+impl PauseScene {
+    pub fn new(needed: (u16, u16)) -> Self {
+        let items = [
+            ("Resume", PauseChoice::Resume),
+            ("Restart", PauseChoice::Restart),
+            ("Settings", PauseChoice::Settings),
+            ("Quit to title", PauseChoice::QuitToHome),
+            ("Quit game", PauseChoice::Quit),
+        ];
+        PauseScene {
+            menu: Menu::new(
+                "PAUSED",
+                items
+                    .into_iter()
+                    .map(|(label, choice)| (label.to_string(), choice))
+                    .collect(),
+            ),
+            needed,
+        }
+    }
+}
+
+impl Scene for PauseScene {
+    // Name the scene the game goes back to, which is the level editor when testing a level
+    fn enter(&mut self, context: &mut Context) {
+        self.menu
+            .set_label(PauseChoice::QuitToHome, format!("Quit to {}", context.home));
+    }
+
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let InputEvent::Key(key) = input else {
+            return Transition::Stay;
+        };
+        let command = context.config.keys.command_for_key(key);
+        if command == Some(Command::Quit) {
+            return Transition::Push(Box::new(ConfirmQuitScene::new(self.needed)));
+        }
+        match self.menu.handle_key(key, command) {
+            MenuInput::Chosen(PauseChoice::Resume) | MenuInput::Back => {
+                if fits(self.needed, &context.screen) {
+                    Transition::Pop
+                } else {
+                    Transition::Stay
+                }
+            }
+            MenuInput::Chosen(PauseChoice::Restart) => match PlayingScene::new(context) {
+                Some(playing) => Transition::Reset(Box::new(playing)),
+                None => Transition::Stay,
+            },
+            MenuInput::Chosen(PauseChoice::Settings) => {
+                Transition::Push(Box::new(SettingsScene::new(self.needed, &context.config)))
+            }
+            MenuInput::Chosen(PauseChoice::QuitToHome) => Transition::Home,
+            MenuInput::Chosen(PauseChoice::Quit) => {
+                Transition::Push(Box::new(ConfirmQuitScene::new(self.needed)))
+            }
+            MenuInput::Adjusted(..) | MenuInput::Moved | MenuInput::Ignored => Transition::Stay,
+        }
+    }
+
+    fn draw(&self, context: &mut Context) {
+        if fits(self.needed, &context.screen) {
+            draw_menu(&self.menu, &mut context.screen);
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// The items in the settings menu. Left and right change a setting and Enter moves it on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Speed,
+    Palette,
+    ShowFps,
+    Back,
+}
+
+// The built in palettes the settings menu goes through
+const PALETTE_NAMES: [&str; 4] = ["default", "light", "colorblind", "mono"];
+
+// The fastest speed the settings menu goes up to
+const MAX_SPEED: f64 = 60.0;
+
+fn setting_label(setting: Setting, config: &Config) -> String {
+    match setting {
+        Setting::Speed => format!("Speed      < {} >", config.speed),
+        Setting::Palette => {
+            let name = PALETTE_NAMES
+                .into_iter()
+                .find(|name| Palette::by_name(name).as_ref() == Some(&config.palette))
+                .unwrap_or("custom");
+            format!("Colours    < {} >", name)
+        }
+        Setting::ShowFps => {
+            let shown = if config.show_fps { "on" } else { "off" };
+            format!("Show FPS   < {} >", shown)
+        }
+        Setting::Back => "Back".to_string(),
+    }
+}
+
+// Change a setting by `step`, which is -1 for left and 1 for right. The changes last until the
+// game is closed and aren't saved to the config file. A new speed starts with the next game.
+fn change_setting(setting: Setting, step: i8, config: &mut Config, screen: &mut DrawScreen) {
+    match setting {
+        Setting::Speed => {
+            let speed = (config.speed.round() + step as f64).clamp(1.0, MAX_SPEED);
+            // Always above 0 so it's valid
+            let _ = config.set_speed(speed);
+        }
+        Setting::Palette => {
+            let count = PALETTE_NAMES.len() as i8;
+            let current = PALETTE_NAMES
+                .iter()
+                .position(|name| Palette::by_name(name).as_ref() == Some(&config.palette))
+                // A custom palette goes back to the first one
+                .map_or(-step.signum(), |index| index as i8);
+            let next = (current + step).rem_euclid(count) as usize;
+            if let Some(palette) = Palette::by_name(PALETTE_NAMES[next]) {
+                config.palette = palette;
+                screen.set_palette(&config.palette);
+            }
+        }
+        Setting::ShowFps => config.show_fps = !config.show_fps,
+        Setting::Back => {}
+    }
+}
+
+// Settings that can be changed in the middle of a game, opened from the pause menu
+pub struct SettingsScene {
+    menu: Menu<Setting>,
+    needed: (u16, u16),
+}
+
+impl SettingsScene {
+    pub fn new(needed: (u16, u16), config: &Config) -> Self {
+        let items = [
+            Setting::Speed,
+            Setting::Palette,
+            Setting::ShowFps,
+            Setting::Back,
+        ];
+        SettingsScene {
+            menu: Menu::new(
+                "SETTINGS",
+                items
+                    .into_iter()
+                    .map(|setting| (setting_label(setting, config), setting))
+                    .collect(),
+            ),
+            needed,
+        }
+    }
+}
+
+impl Scene for SettingsScene {
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let InputEvent::Key(key) = input else {
+            return Transition::Stay;
+        };
+        let command = context.config.keys.command_for_key(key);
+        let (setting, step) = match self.menu.handle_key(key, command) {
+            MenuInput::Chosen(Setting::Back) | MenuInput::Back => return Transition::Pop,
+            MenuInput::Chosen(setting) => (setting, 1),
+            MenuInput::Adjusted(setting, step) => (setting, step),
+            MenuInput::Moved | MenuInput::Ignored => return Transition::Stay,
+        };
+        change_setting(setting, step, &mut context.config, &mut context.screen);
+        self.menu
+            .set_label(setting, setting_label(setting, &context.config));
+        Transition::Stay
+    }
+
+    fn draw(&self, context: &mut Context) {
+        if fits(self.needed, &context.screen) {
+            draw_menu(&self.menu, &mut context.screen);
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// Asks whether to quit in the middle of a game. Y, the quit key again or the Quit item quits, and
+// anything that backs out goes back to where the player was.
+pub struct ConfirmQuitScene {
+    menu: Menu<bool>,
+    needed: (u16, u16),
+}
+
+impl ConfirmQuitScene {
+    pub fn new(needed: (u16, u16)) -> Self {
+        ConfirmQuitScene {
+            menu: Menu::new(
+                "QUIT THE GAME?",
+                vec![
+                    ("Keep playing".to_string(), false),
+                    ("Quit".to_string(), true),
+                ],
+            ),
+            needed,
+        }
+    }
+}
+
+impl Scene for ConfirmQuitScene {
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let InputEvent::Key(key) = input else {
+            return Transition::Stay;
+        };
+        let command = context.config.keys.command_for_key(key);
+        let quit = match (key.code, command) {
+            (_, Some(Command::Quit)) | (KeyCode::Char('y' | 'Y'), _) => true,
+            (KeyCode::Char('n' | 'N'), _) => false,
+            _ => match self.menu.handle_key(key, command) {
+                MenuInput::Chosen(quit) => quit,
+                MenuInput::Back => false,
+                MenuInput::Adjusted(..) | MenuInput::Moved | MenuInput::Ignored => {
+                    return Transition::Stay
+                }
+            },
+        };
+        if quit {
+            Transition::Quit
+        } else {
+            Transition::Pop
+        }
+    }
+
+    fn draw(&self, context: &mut Context) {
+        if fits(self.needed, &context.screen) {
+            draw_menu(&self.menu, &mut context.screen);
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
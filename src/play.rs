@@ -0,0 +1,365 @@
+// Copyright (c) 2022 DanWillans
+use crate::campaign::LevelCompleteScene;
+use crate::input::{edit_text, TextEdit};
+use crate::pause::{ConfirmQuitScene, PauseScene};
+use crate::rendering::text_width;
+use crate::scene::{Context, Scene, Transition};
+use crate::scores::{today, MAX_NAME_LENGTH};
+use crate::view::{draw_screen_too_small, MIN_SCREEN_HEIGHT, MIN_SCREEN_WIDTH};
+use crate::{
+    BoardView, Command, Config, DrawColor, DrawScreen, GameBoard, GameEvent, HighScores,
+    InputEvent, Level, Replay, ScoreEntry,
+};
+use std::path::PathBuf;
+
+// The size of the board for a new game from the config. None fills the terminal.
+fn new_board_size(config: &Config) -> Option<(u16, u16)> {
+    match &config.level {
+        Some(level) => Some((level.columns(), level.rows())),
+        None => config.board_size,
+    }
+}
+
+// A view for a new game. The board is the size of the level or the size from the config, or as big
+// as the terminal allows if there isn't one. None if it doesn't fit.
+pub fn new_board_view(config: &Config, screen: &DrawScreen) -> Option<BoardView> {
+    match new_board_size(config) {
+        Some((columns, rows)) => BoardView::fit_board(columns, rows, config.theme, screen),
+        None => BoardView::fill_screen(config.theme, screen),
+    }
+}
+
+// The smallest screen a new game fits on
+pub fn needed_screen_size(config: &Config) -> (u16, u16) {
+    match new_board_size(config) {
+        Some((columns, rows)) => BoardView::screen_size_for(columns, rows, config.theme),
+        None => (MIN_SCREEN_WIDTH, MIN_SCREEN_HEIGHT),
+    }
+}
+
+// The high-score table and what's shown under the game over message
+pub struct ScoreBoard {
+    path: Option<PathBuf>,
+    scores: HighScores,
+    // The name being typed for a new high score
+    name_entry: Option<String>,
+    // Where the last game ended up in the table
+    highlight: Option<usize>,
+    // Why the table couldn't be loaded or saved
+    error: Option<String>,
+}
+
+impl ScoreBoard {
+    pub fn load() -> Self {
+        let path = HighScores::default_path();
+        let (scores, error) = match path.as_deref().map(HighScores::load) {
+            Some(Ok(scores)) => (scores, None),
+            Some(Err(err)) => (HighScores::default(), Some(err)),
+            None => (HighScores::default(), None),
+        };
+        ScoreBoard {
+            path,
+            scores,
+            name_entry: None,
+            highlight: None,
+            error,
+        }
+    }
+
+    pub fn scores(&self) -> &HighScores {
+        &self.scores
+    }
+
+    // A game just ended with `points`. Ask for a name if it made it into the table.
+    fn game_over(&mut self, points: u32) {
+        self.highlight = None;
+        self.name_entry = None;
+        if self.path.is_some() && self.scores.qualifies(points) {
+            self.name_entry = Some(String::new());
+        }
+    }
+
+    // Save the game that just ended under the name that was typed. `time` is the seconds it was
+    // played for.
+    fn save(&mut self, board: &GameBoard, points: u32, time: f64) {
+        let (Some(path), Some(name)) = (&self.path, self.name_entry.take()) else {
+            return;
+        };
+        let name = match name.trim() {
+            "" => std::env::var("USER").unwrap_or_else(|_| "Player".to_string()),
+            name => name.to_string(),
+        };
+        let entry = ScoreEntry {
+            name,
+            score: points,
+            length: board.snake_len(),
+            duration: time.round() as u32,
+            seed: board.seed(),
+            mode: match board.level().name() {
+                "" => board.mode().to_string(),
+                level => format!("{} {}", board.mode(), level),
+            },
+            date: today(),
+        };
+        match HighScores::record(path, entry) {
+            Ok((scores, highlight)) => {
+                self.scores = scores;
+                self.highlight = highlight;
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+
+    // Draw the game over message with the name entry or the table under it
+    fn draw(&self, board_view: &BoardView, board: &GameBoard, home: &str, screen: &mut DrawScreen) {
+        board_view.draw_game_over(board, home, screen);
+        if let Some(name) = &self.name_entry {
+            board_view.draw_name_entry(name, screen);
+        } else if let Some(err) = &self.error {
+            board_view.draw_centred_text(4, err.clone(), DrawColor::Red, screen);
+        } else {
+            board_view.draw_high_scores(&self.scores, self.highlight, screen);
+        }
+    }
+}
+
+// Draw `board` and the score centred on the screen, or ask for a bigger terminal if it doesn't fit.
+// Returns the view it was drawn with.
+fn draw_board(board: &GameBoard, points: u32, context: &mut Context) -> Option<BoardView> {
+    let theme = context.config.theme;
+    let level = context.config.speed_curve.level(board.score());
+    let screen = &mut context.screen;
+    let board_view = BoardView::fit_board(board.columns(), board.rows(), theme, screen);
+    match &board_view {
+        Some(board_view) => {
+            board_view.draw(board, screen);
+            board_view.draw_score(points, level, screen);
+        }
+        None => draw_screen_too_small(
+            BoardView::screen_size_for(board.columns(), board.rows(), theme),
+            screen,
+        ),
+    }
+    board_view
+}
+
+// A game being played. The GameBoard is stepped once a tick and every turn is recorded so the
+// game can be watched again. Pausing, quitting and the end of the game open scenes on top of it.
+pub struct PlayingScene {
+    board: GameBoard,
+    replay: Replay,
+    // The board's score counts the food eaten. Points are what the player scores, which is more
+    // for food eaten at a higher speed.
+    points: u32,
+    // Moves per second the game started at. Changing the speed in the settings menu only applies
+    // from the next game so the replay plays the game the way it went.
+    speed: f64,
+    // Seconds of play so far, which is how long a campaign level took
+    time: f64,
+    // The replay has been saved, which happens once when the game ends or is left
+    saved: bool,
+}
+
+impl PlayingScene {
+    // A new game sized for the terminal as it is now. None if it doesn't fit.
+    // Use the same seed for every game if one was given so games can be reproduced
+    pub fn new(context: &Context) -> Option<Self> {
+        let config = &context.config;
+        let board_view = new_board_view(config, &context.screen)?;
+        let level = match &config.level {
+            Some(level) => level.clone(),
+            None => Level::empty(board_view.columns(), board_view.rows()),
+        };
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let board = GameBoard::with_seed(&level, seed).with_wrap(config.wrap);
+        let replay = Replay::new(&board, config.speed, &config.speed_curve, board.mode());
+        Some(PlayingScene {
+            board,
+            replay,
+            points: 0,
+            speed: config.speed,
+            time: 0.0,
+            saved: false,
+        })
+    }
+
+    // Save the game to the replay directory, however far it got. Games that were left before the
+    // snake moved aren't worth keeping. A replay that can't be saved shouldn't get in the way of
+    // playing so any error is ignored.
+    fn save_replay(&mut self) {
+        if self.saved || self.board.steps() == 0 {
+            return;
+        }
+        self.saved = true;
+        self.replay.finish(&self.board);
+        if let Some(directory) = Replay::default_directory() {
+            let _ = self.replay.save_to_directory(&directory);
+        }
+    }
+
+    // The screen size the board needs, which menus check before letting the game carry on
+    fn needed_screen_size(&self, context: &Context) -> (u16, u16) {
+        BoardView::screen_size_for(
+            self.board.columns(),
+            self.board.rows(),
+            context.config.theme,
+        )
+    }
+}
+
+impl Scene for PlayingScene {
+    // Games that are restarted or quit part way through are recorded too
+    fn exit(&mut self, _context: &mut Context) {
+        self.save_replay();
+    }
+
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let needed = self.needed_screen_size(context);
+        let key = match input {
+            InputEvent::Key(key) => key,
+            // Give the player a chance to get ready again
+            InputEvent::Resize(..) | InputEvent::FocusLost => {
+                return Transition::Push(Box::new(PauseScene::new(needed)))
+            }
+        };
+        match context.config.keys.command_for_key(key) {
+            Some(Command::Turn(direction)) => {
+                // Only turns the board accepts change the game so only they're recorded
+                let accepted = self.board.update_snake_direction(direction);
+                if accepted {
+                    self.replay.record_turn(&self.board, direction);
+                }
+                Transition::Stay
+            }
+            Some(Command::Restart) => match PlayingScene::new(context) {
+                Some(playing) => Transition::Switch(Box::new(playing)),
+                None => Transition::Stay,
+            },
+            Some(Command::Pause) => Transition::Push(Box::new(PauseScene::new(needed))),
+            Some(Command::Quit) => Transition::Push(Box::new(ConfirmQuitScene::new(needed))),
+            Some(Command::Start) | None => Transition::Stay,
+        }
+    }
+
+    // The snake moves once a tick, getting faster as it eats
+    fn tick_rate(&self, context: &Context) -> Option<f64> {
+        let config = &context.config;
+        Some(config.speed_curve.speed(self.speed, self.board.score()))
+    }
+
+    // Step the GameBoard. This moves and grows the snake, detects collisions and respawns food.
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        // Wait in the pause menu until the terminal is big enough for the board
+        let needed = self.needed_screen_size(context);
+        if context.screen.width() < needed.0 || context.screen.height() < needed.1 {
+            return Transition::Push(Box::new(PauseScene::new(needed)));
+        }
+        let config = &context.config;
+        let eaten = self.board.score();
+        let points = config.speed_curve.points_for_food(self.speed, eaten);
+        self.time += 1.0 / config.speed_curve.speed(self.speed, eaten);
+        let event = self.board.step(None);
+        if self.board.score() > eaten {
+            self.points += points;
+        }
+        if event == GameEvent::LevelComplete && context.campaign.is_some() {
+            self.save_replay();
+            return Transition::Push(Box::new(LevelCompleteScene::new(
+                self.board.clone(),
+                self.points,
+                self.time,
+            )));
+        }
+        if event.is_game_over() {
+            self.save_replay();
+            return Transition::Push(Box::new(GameOverScene::new(
+                self.board.clone(),
+                self.points,
+                self.time,
+            )));
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, context: &mut Context) {
+        draw_board(&self.board, self.points, context);
+        if context.config.show_fps {
+            let fps_text = format!("fps: {:.2}", context.fps);
+            let screen = &mut context.screen;
+            screen.update_with_string(
+                screen.width().saturating_sub(text_width(&fps_text) + 2),
+                1,
+                fps_text,
+                DrawColor::White,
+            );
+        }
+    }
+}
+
+// Shown over the finished game. Asks for a name if the score made the high-score table and then
+// shows the table. Enter plays again, Esc goes back to the title screen, or the level editor when
+// testing a level, and Q quits.
+pub struct GameOverScene {
+    // The game as it ended
+    board: GameBoard,
+    points: u32,
+    // Seconds of play
+    time: f64,
+}
+
+impl GameOverScene {
+    pub fn new(board: GameBoard, points: u32, time: f64) -> Self {
+        GameOverScene {
+            board,
+            points,
+            time,
+        }
+    }
+}
+
+impl Scene for GameOverScene {
+    fn enter(&mut self, context: &mut Context) {
+        context.score_board.game_over(self.points);
+    }
+
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let InputEvent::Key(key) = input else {
+            return Transition::Stay;
+        };
+        // While a name is being typed for the high-score table every key goes to it
+        if let Some(name) = &mut context.score_board.name_entry {
+            if edit_text(name, key, MAX_NAME_LENGTH) == TextEdit::Finished {
+                context
+                    .score_board
+                    .save(&self.board, self.points, self.time);
+            }
+            return Transition::Stay;
+        }
+        match context.config.keys.command_for_key(key) {
+            Some(Command::Restart) => match PlayingScene::new(context) {
+                Some(playing) => Transition::Reset(Box::new(playing)),
+                None => Transition::Stay,
+            },
+            Some(Command::Pause) => Transition::Home,
+            Some(Command::Quit) => Transition::Quit,
+            _ => Transition::Stay,
+        }
+    }
+
+    fn draw(&self, context: &mut Context) {
+        if let Some(board_view) = BoardView::fit_board(
+            self.board.columns(),
+            self.board.rows(),
+            context.config.theme,
+            &context.screen,
+        ) {
+            context
+                .score_board
+                .draw(&board_view, &self.board, context.home, &mut context.screen);
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -1,8 +1,12 @@
 // Copyright (c) 2022 DanWillans
 use std::io::{Write, Stdout, stdout};
-use crossterm::{execute, queue, terminal, cursor, style::{self, Stylize, StyledContent}};
+use std::sync::Once;
+use crossterm::{execute, queue, terminal, cursor, style, event};
+use crate::palette::{ColorSupport, Palette};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum DrawColor {
   White,
   Green,
@@ -11,94 +15,489 @@ pub enum DrawColor {
   GameBorder,
 }
 
+// Number of terminal columns a grapheme cluster takes up. Some emoji sequences add up to more than
+// two columns but terminals draw them as a single double width glyph.
+pub fn grapheme_width(grapheme: &str) -> u16 {
+  grapheme.width().min(2) as u16
+}
+
+// Number of terminal columns a string takes up
+pub fn text_width(text: &str) -> u16 {
+  text.graphemes(true).map(grapheme_width).sum()
+}
+
+// Plain ASCII stand in for a box drawing character, for terminals and fonts that don't have them
+fn ascii_box_drawing(character: char) -> char {
+  match character {
+    '─' | '━' | '═' => '-',
+    '│' | '┃' | '║' => '|',
+    '┄' | '┈' => '.',
+    '┆' | '┊' => ':',
+    '\u{2500}'..='\u{257F}' => '+',
+    _ => character,
+  }
+}
+
+// A single cell of the DrawScreen.
+// `symbol` is the grapheme cluster drawn in the cell and `width` is how many columns it covers.
+// The cells covered by the rest of a wide character are continuation cells with a width of 0.
+#[derive(Clone, PartialEq, Eq)]
 pub struct DrawPixel{
-  pub character: char,
+  pub symbol: String,
+  pub width: u8,
   pub color: DrawColor,
 }
 
 impl DrawPixel{
-  fn get_stylize(&self) -> StyledContent<char> {
-    match &self.color{
-      DrawColor::Red => self.character.red(),
-      DrawColor::White => self.character.white(),
-      DrawColor::Green => self.character.green(),
-      DrawColor::Border => self.character.green(),
-      DrawColor::GameBorder => self.character.dark_yellow(),
-    }
+  fn blank() -> Self {
+    DrawPixel{symbol: " ".to_string(), width: 1, color: DrawColor::White}
+  }
+
+  fn continuation() -> Self {
+    DrawPixel{symbol: String::new(), width: 0, color: DrawColor::White}
+  }
+
+  fn is_continuation(&self) -> bool {
+    self.width == 0
+  }
+
+  // Change the cell in place so its String can be reused
+  fn set(&mut self, symbol: &str, width: u8, color: DrawColor) {
+    self.symbol.clear();
+    self.symbol.push_str(symbol);
+    self.width = width;
+    self.color = color;
   }
 }
 
-pub struct DrawScreen{
+// Put the terminal back the way we found it. It's safe to call this more than once.
+pub fn restore_terminal() {
+  let _ = execute!(stdout(), event::DisableFocusChange, style::ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+  let _ = terminal::disable_raw_mode();
+}
+
+// Restore the terminal before the panic message is printed, otherwise it's lost on the alternate
+// screen and the shell is left in raw mode.
+fn install_panic_hook() {
+  static INSTALL: Once = Once::new();
+  INSTALL.call_once(|| {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+      restore_terminal();
+      default_hook(info);
+    }));
+  });
+}
+
+// The cells of a screen without the terminal. `width` and `height` are the number of columns and
+// rows that can be drawn to. Anything drawn outside of them is ignored.
+// Drawing is double buffered. Updates only change the `back` buffer and `queue_changes` writes the
+// cells that differ from the `front` buffer, which holds what's already been written.
+// DrawColors are turned into terminal colours by the `palette`, which only holds colours the
+// terminal can show. When `color_support` is Monochrome no colours are written at all.
+pub struct ScreenBuffer{
   width: u16,
   height: u16,
-  io: Stdout,
+  ascii_borders: bool,
+  palette: Palette,
+  color_support: ColorSupport,
+  front: Vec<DrawPixel>,
+  back: Vec<DrawPixel>,
 }
 
-impl DrawScreen {
-  pub fn new(width: u16, height: u16) -> Self {
-    let io = stdout();
-    let mut screen = Self{width, height, io};
-    // Let's make sure we clear the draw screen first
-    if let Err(res) = execute!(stdout(), terminal::Clear(terminal::ClearType::All)) {
-      println!("Error clearing terminal {}!", res.to_string());
-    }
+impl ScreenBuffer {
+  pub fn new(width: u16, height: u16, color_support: ColorSupport) -> Self {
+    let cells = width as usize * height as usize;
+    let palette = Palette::default().downgrade(color_support);
+    let mut buffer = Self{width, height, ascii_borders: false, palette, color_support, front: vec![DrawPixel::blank(); cells], back: vec![DrawPixel::blank(); cells]};
+    buffer.draw_border();
+    buffer
+  }
+
+  pub fn width(&self) -> u16 {
+    self.width
+  }
 
-    screen.draw_border();
+  pub fn height(&self) -> u16 {
+    self.height
+  }
+
+  // Change the size of the buffer. Both buffers start out blank with the border redrawn, so
+  // everything else needs drawing again.
+  pub fn resize(&mut self, width: u16, height: u16){
+    self.width = width;
+    self.height = height;
+    let cells = width as usize * height as usize;
+    self.front = vec![DrawPixel::blank(); cells];
+    self.back = vec![DrawPixel::blank(); cells];
+    self.draw_border();
+  }
+
+  // Draw box drawing characters as plain ASCII from now on
+  pub fn set_ascii_borders(&mut self, ascii_borders: bool){
+    self.ascii_borders = ascii_borders;
+    self.draw_border();
+  }
+
+  // Draw with the colours from `palette` from now on, converted to ones the terminal can show
+  pub fn set_palette(&mut self, palette: &Palette){
+    self.palette = palette.downgrade(self.color_support);
+    // Forget what's been written so every cell is sent again in the new colours
+    for pixel in self.front.iter_mut() {
+      pixel.symbol.clear();
+      pixel.width = u8::MAX;
+    }
+  }
 
-    screen
+  pub fn color_support(&self) -> ColorSupport {
+    self.color_support
   }
 
   pub fn draw_border(&mut self){
+    if self.width < 2 || self.height < 2 {
+      return;
+    }
+    let right = self.width - 1;
+    let bottom = self.height - 1;
     // Update border of the draw screen
     // Draw corners of the draw screen
     self.update(0, 0, '╔', DrawColor::Border);
-    self.update(self.width - 1, 0, '╗', DrawColor::Border);
-    self.update(self.width - 1, self.height, '╝', DrawColor::Border);
-    self.update(0, self.height, '╚', DrawColor::Border);
+    self.update(right, 0, '╗', DrawColor::Border);
+    self.update(right, bottom, '╝', DrawColor::Border);
+    self.update(0, bottom, '╚', DrawColor::Border);
     // Draw left and right borders.
-    // Divide by 2 because the character we're using is double length
-    for i in 1..self.height{
+    for i in 1..bottom{
       self.update(0, i, '║', DrawColor::Border);
-      self.update(self.width - 1, i, '║', DrawColor::Border);
+      self.update(right, i, '║', DrawColor::Border);
     }
     // Draw top and bottom border
-    for i in 1..self.width - 1 {
+    for i in 1..right {
       self.update(i, 0, '═', DrawColor::Border);
-      self.update(i, self.height, '═', DrawColor::Border);
+      self.update(i, bottom, '═', DrawColor::Border);
+    }
+  }
+
+  // Write every cell that changed since the last call to `io`.
+  // Runs of changed cells on a row are printed after a single cursor move and the colour is only
+  // changed when it differs from the previous cell printed.
+  pub fn queue_changes<W: Write>(&mut self, io: &mut W) -> crossterm::Result<()> {
+    // Where the terminal cursor will be after the last print, if we know
+    let mut cursor_position = None;
+    let mut current_color = None;
+    for y in 0..self.height {
+      for x in 0..self.width {
+        let index = self.index(x, y);
+        let pixel = &self.back[index];
+        if *pixel == self.front[index] {
+          continue;
+        }
+        if !pixel.is_continuation() {
+          if cursor_position != Some((x, y)) {
+            queue!(io, cursor::MoveTo(x, y))?;
+          }
+          let color = self.palette.get(pixel.color).to_crossterm();
+          if self.color_support != ColorSupport::Monochrome && current_color != Some(color) {
+            queue!(io, style::SetForegroundColor(color))?;
+            current_color = Some(color);
+          }
+          queue!(io, style::Print(&pixel.symbol))?;
+          cursor_position = Some((x + pixel.width as u16, y));
+        }
+        // Continuation cells were covered when the wide character before them was printed
+        self.front[index].clone_from(pixel);
+      }
+    }
+    Ok(())
+  }
+
+  fn index(&self, x: u16, y: u16) -> usize {
+    y as usize * self.width as usize + x as usize
+  }
+
+  // If the cell is part of a wide character then blank the whole character so half of it isn't
+  // left behind when something is drawn over the other half
+  fn break_wide_character(&mut self, x: u16, y: u16) {
+    let mut start = x;
+    while start > 0 && self.back[self.index(start, y)].is_continuation() {
+      start -= 1;
+    }
+    let width = self.back[self.index(start, y)].width.max(1) as u16;
+    if width == 1 && start == x {
+      return;
+    }
+    for column in start..(start + width).min(self.width) {
+      let index = self.index(column, y);
+      self.back[index].set(" ", 1, DrawColor::White);
+    }
+  }
+
+  // Put a grapheme cluster that is `width` columns wide into the back buffer
+  fn update_grapheme(&mut self, x: u16, y: u16, grapheme: &str, width: u16, color: DrawColor){
+    if width == 0 || x >= self.width || y >= self.height {
+      return;
+    }
+    let end = (x + width).min(self.width);
+    for column in x..end {
+      self.break_wide_character(column, y);
+    }
+    if x + width > self.width {
+      // There's no room for the whole character so leave a blank
+      let index = self.index(x, y);
+      self.back[index].set(" ", 1, DrawColor::White);
+      return;
+    }
+    let index = self.index(x, y);
+    self.back[index].set(grapheme, width as u8, color);
+    for column in x + 1..end {
+      let index = self.index(column, y);
+      self.back[index] = DrawPixel::continuation();
+    }
+  }
+
+  // Draw a character at x, y. Wide characters also cover the cells to their right.
+  pub fn update(&mut self, x: u16, y: u16, character: char, color: DrawColor){
+    let character = if self.ascii_borders { ascii_box_drawing(character) } else { character };
+    let mut buffer = [0; 4];
+    let grapheme = character.encode_utf8(&mut buffer);
+    self.update_grapheme(x, y, grapheme, grapheme_width(grapheme), color);
+  }
+
+  // Draw a string starting at x, y. Each grapheme cluster moves along by its display width.
+  pub fn update_with_string(&mut self, x: u16, y: u16, string: String, color: DrawColor){
+    let mut column = x;
+    for grapheme in string.graphemes(true) {
+      let width = grapheme_width(grapheme);
+      self.update_grapheme(column, y, grapheme, width, color);
+      column = column.saturating_add(width);
     }
   }
 
+  // Clear everything inside the border
+  pub fn clear(&mut self){
+    for y in 1..self.height.saturating_sub(1) {
+      for x in 1..self.width.saturating_sub(1) {
+        self.update(x, y, ' ', DrawColor::White);
+      }
+    }
+  }
+
+}
+
+// The DrawScreen owns the terminal while it's alive. Creating one switches to the alternate screen,
+// enables raw mode, hides the cursor and turns on focus reporting. Dropping it restores everything.
+// What's drawn is kept in a ScreenBuffer and `draw` sends the cells that changed to the terminal.
+pub struct DrawScreen{
+  buffer: ScreenBuffer,
+  io: Stdout,
+}
+
+impl DrawScreen {
+  pub fn new(width: u16, height: u16) -> Self {
+    let buffer = ScreenBuffer::new(width, height, ColorSupport::detect());
+    install_panic_hook();
+    if let Err(res) = terminal::enable_raw_mode() {
+      println!("Error enabling raw mode {}!", res);
+    }
+    // Let's make sure we clear the draw screen first
+    if let Err(res) = execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All)) {
+      println!("Error clearing terminal {}!", res);
+    }
+    // Ask the terminal to say when it loses focus so the game can pause itself. Terminals that
+    // don't support it ignore this.
+    let _ = execute!(stdout(), event::EnableFocusChange);
+
+    Self{buffer, io: stdout()}
+  }
+
+  // Create a DrawScreen that covers the whole terminal
+  pub fn fullscreen() -> Self {
+    let (width, height) = terminal::size().unwrap_or((80, 24));
+    Self::new(width, height)
+  }
+
+  pub fn width(&self) -> u16 {
+    self.buffer.width()
+  }
+
+  pub fn height(&self) -> u16 {
+    self.buffer.height()
+  }
+
+  // Change the size of the DrawScreen after the terminal has been resized. The terminal is cleared
+  // and the border redrawn so everything else needs drawing again.
+  pub fn resize(&mut self, width: u16, height: u16){
+    self.buffer.resize(width, height);
+    if let Err(res) = queue!(self.io, terminal::Clear(terminal::ClearType::All)) {
+      println!("Error clearing terminal {}!", res);
+    }
+  }
+
+  pub fn set_ascii_borders(&mut self, ascii_borders: bool){
+    self.buffer.set_ascii_borders(ascii_borders);
+  }
+
+  pub fn set_palette(&mut self, palette: &Palette){
+    self.buffer.set_palette(palette);
+  }
+
+  pub fn color_support(&self) -> ColorSupport {
+    self.buffer.color_support()
+  }
+
+  pub fn draw_border(&mut self){
+    self.buffer.draw_border();
+  }
+
+  // Send every cell that changed since the last draw to the terminal
   pub fn draw(&mut self){
+    if let Err(_res) = self.buffer.queue_changes(&mut self.io) {
+      println!("Error queueing to stdout io");
+    }
     if let Err(_res) = self.io.flush(){
       println!("Error flushing to stdout io");
     }
   }
 
   pub fn update(&mut self, x: u16, y: u16, character: char, color: DrawColor){
-    if let Err(_res) = queue!(self.io, cursor::MoveTo(x,y), style::PrintStyledContent(DrawPixel{character, color}.get_stylize())) { 
-      println!("Error queueing to stdout io");
-    }
+    self.buffer.update(x, y, character, color);
   }
 
   pub fn update_with_string(&mut self, x: u16, y: u16, string: String, color: DrawColor){
-    let chars = string.as_bytes();
-    for i in 0..string.len() {
-      let c = chars[i] as char;
-      self.update(x + (i as u16), y, c, color);
-    }
+    self.buffer.update_with_string(x, y, string, color);
   }
 
   pub fn clear(&mut self){
-    for y in 1..self.height {
-      for x in 1..self.width - 1 {
-                self.update(
-                    x,
-                    y,
-                    ' ',
-                    DrawColor::White,
-                );
-            }
-        }
+    self.buffer.clear();
   }
 
-}
\ No newline at end of file
+}
+
+impl Drop for DrawScreen {
+  fn drop(&mut self) {
+    self.draw();
+    restore_terminal();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A 6x4 monochrome buffer with its border already written out
+  fn buffer() -> ScreenBuffer {
+    let mut buffer = ScreenBuffer::new(6, 4, ColorSupport::Monochrome);
+    buffer.queue_changes(&mut Vec::new()).unwrap();
+    buffer
+  }
+
+  fn changes(buffer: &mut ScreenBuffer) -> String {
+    let mut io = Vec::new();
+    buffer.queue_changes(&mut io).unwrap();
+    String::from_utf8(io).unwrap()
+  }
+
+  #[test]
+  fn first_frame_writes_the_border() {
+    let mut buffer = ScreenBuffer::new(3, 2, ColorSupport::Monochrome);
+    assert_eq!(changes(&mut buffer), "\x1b[1;1H╔═╗\x1b[2;1H╚═╝");
+  }
+
+  #[test]
+  fn unchanged_frame_writes_nothing() {
+    let mut buffer = buffer();
+    assert_eq!(changes(&mut buffer), "");
+    // Drawing what's already there isn't a change either
+    buffer.update(0, 0, '╔', DrawColor::Border);
+    buffer.update_with_string(1, 1, " ".to_string(), DrawColor::White);
+    assert_eq!(changes(&mut buffer), "");
+  }
+
+  #[test]
+  fn only_changed_cells_are_written() {
+    let mut buffer = buffer();
+    buffer.update(2, 1, 'x', DrawColor::White);
+    buffer.update(1, 2, 'y', DrawColor::White);
+    assert_eq!(changes(&mut buffer), "\x1b[2;3Hx\x1b[3;2Hy");
+    assert_eq!(changes(&mut buffer), "");
+  }
+
+  #[test]
+  fn runs_of_changed_cells_share_a_cursor_move() {
+    let mut buffer = buffer();
+    buffer.update_with_string(1, 1, "ab".to_string(), DrawColor::White);
+    buffer.update(4, 1, 'c', DrawColor::White);
+    assert_eq!(changes(&mut buffer), "\x1b[2;2Hab\x1b[2;5Hc");
+  }
+
+  #[test]
+  fn colour_is_only_set_when_it_changes() {
+    let mut buffer = ScreenBuffer::new(6, 4, ColorSupport::Ansi16);
+    buffer.queue_changes(&mut Vec::new()).unwrap();
+    buffer.update_with_string(1, 1, "ab".to_string(), DrawColor::Green);
+    buffer.update(3, 1, 'c', DrawColor::Red);
+    buffer.update(1, 2, 'd', DrawColor::Red);
+    assert_eq!(changes(&mut buffer).matches("\x1b[38;").count(), 2);
+  }
+
+  // The symbols and widths of the cells on row `y`
+  fn row(buffer: &ScreenBuffer, y: u16) -> Vec<(&str, u8)> {
+    (0..buffer.width()).map(|x| &buffer.back[buffer.index(x, y)]).map(|pixel| (pixel.symbol.as_str(), pixel.width)).collect()
+  }
+
+  #[test]
+  fn wide_characters_cover_the_next_cell() {
+    let mut buffer = buffer();
+    buffer.update(1, 1, '界', DrawColor::White);
+    assert_eq!(row(&buffer, 1), [("║", 1), ("界", 2), ("", 0), (" ", 1), (" ", 1), ("║", 1)]);
+    // Only the first cell is printed and the cursor has moved past the second
+    buffer.update(3, 1, 'x', DrawColor::White);
+    assert_eq!(changes(&mut buffer), "\x1b[2;2H界x");
+  }
+
+  #[test]
+  fn drawing_over_half_a_wide_character_blanks_the_other_half() {
+    let mut buffer = buffer();
+    buffer.update(1, 1, '界', DrawColor::White);
+    buffer.update(2, 1, 'x', DrawColor::White);
+    assert_eq!(row(&buffer, 1)[1..3], [(" ", 1), ("x", 1)]);
+
+    buffer.update(1, 2, '界', DrawColor::White);
+    buffer.update(1, 2, 'y', DrawColor::White);
+    assert_eq!(row(&buffer, 2)[1..3], [("y", 1), (" ", 1)]);
+
+    // A wide character half over another one breaks it too
+    buffer.update(1, 1, '界', DrawColor::White);
+    buffer.update(2, 1, '世', DrawColor::White);
+    assert_eq!(row(&buffer, 1)[1..4], [(" ", 1), ("世", 2), ("", 0)]);
+  }
+
+  #[test]
+  fn wide_character_in_the_last_column_leaves_a_blank() {
+    let mut buffer = buffer();
+    buffer.update(5, 1, '界', DrawColor::White);
+    assert_eq!(row(&buffer, 1)[5], (" ", 1));
+    buffer.update_with_string(4, 2, "a界".to_string(), DrawColor::White);
+    assert_eq!(row(&buffer, 2)[4..], [("a", 1), (" ", 1)]);
+    assert_eq!(changes(&mut buffer), "\x1b[2;6H \x1b[3;5Ha ");
+  }
+
+  #[test]
+  fn strings_move_along_by_grapheme_cluster() {
+    let mut buffer = buffer();
+    // e with a combining acute accent is one cell
+    buffer.update_with_string(1, 1, "e\u{301}x".to_string(), DrawColor::White);
+    assert_eq!(row(&buffer, 1)[1..3], [("e\u{301}", 1), ("x", 1)]);
+    // A ZWJ emoji sequence is a single double width glyph
+    buffer.update_with_string(1, 2, "👩\u{200d}💻y".to_string(), DrawColor::White);
+    assert_eq!(row(&buffer, 2)[1..4], [("👩\u{200d}💻", 2), ("", 0), ("y", 1)]);
+    assert_eq!(changes(&mut buffer), "\x1b[2;2He\u{301}x\x1b[3;2H👩\u{200d}💻y");
+  }
+
+  #[test]
+  fn resize_starts_again_from_a_blank_terminal() {
+    let mut buffer = buffer();
+    buffer.resize(3, 2);
+    buffer.update(1, 0, 'x', DrawColor::White);
+    assert_eq!(changes(&mut buffer), "\x1b[1;1H╔x╗\x1b[2;1H╚═╝");
+  }
+}
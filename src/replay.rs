@@ -0,0 +1,378 @@
+// Copyright (c) 2022 DanWillans
+use crate::config::xdg_directory;
+use crate::{Direction, GameBoard, GameEvent, Level, SpeedCurve};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Bumped whenever a change to the game would make old replays play out differently
+const REPLAY_VERSION: u32 = 1;
+
+// How many replays are kept in the replay directory. The oldest are deleted first.
+pub const MAX_REPLAYS: usize = 50;
+
+// A recording of a game. The board is seeded so the only thing that needs storing is when the
+// player turned. Each turn is kept with the step it was made before, which is all it takes to play
+// the game again exactly.
+// The score and length of the game are stored too so a replay can check the game still plays the
+// same way, which makes replays handy as regression tests.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub columns: u16,
+    pub rows: u16,
+    // The walls the game was played with
+    pub level: Level,
+    // Moves per second the game started at and how it sped up
+    pub speed: f64,
+    pub speed_curve: SpeedCurve,
+    pub mode: String,
+    // (step, direction) for every turn, in the order they were made
+    pub turns: Vec<(u32, Direction)>,
+    // How far the game got, which is where it ended or where it was left. None until it's saved.
+    pub score: Option<u32>,
+    pub steps: Option<u32>,
+}
+
+// The layout of a replay file. Turns are written as a string like "3N 10E 10S" to keep it small.
+#[derive(Serialize, Deserialize)]
+struct ReplayFile {
+    version: u32,
+    #[serde(with = "crate::config::toml_seed")]
+    seed: u64,
+    width: u16,
+    height: u16,
+    // The level's name and map, left out for games on an open board
+    level_name: Option<String>,
+    level: Option<String>,
+    // Food that opens the exit, only for campaign levels
+    target: Option<u16>,
+    speed: f64,
+    // Left out of replays from before the game sped up, which played at a constant speed
+    speed_curve: Option<Vec<f64>>,
+    foods_per_level: Option<u16>,
+    mode: String,
+    score: Option<u32>,
+    steps: Option<u32>,
+    turns: String,
+}
+
+fn direction_letter(direction: Direction) -> char {
+    match direction {
+        Direction::North => 'N',
+        Direction::East => 'E',
+        Direction::South => 'S',
+        Direction::West => 'W',
+    }
+}
+
+// Read a turn like "10E"
+fn parse_turn(turn: &str) -> Option<(u32, Direction)> {
+    let direction = match turn.chars().last()? {
+        'N' => Direction::North,
+        'E' => Direction::East,
+        'S' => Direction::South,
+        'W' => Direction::West,
+        _ => return None,
+    };
+    let step = turn[..turn.len() - 1].parse().ok()?;
+    Some((step, direction))
+}
+
+impl Replay {
+    // Start recording a game that's about to be played on `board`
+    pub fn new(board: &GameBoard, speed: f64, speed_curve: &SpeedCurve, mode: &str) -> Self {
+        Replay {
+            seed: board.seed(),
+            columns: board.columns(),
+            rows: board.rows(),
+            level: board.level().clone(),
+            speed,
+            speed_curve: speed_curve.clone(),
+            mode: mode.to_string(),
+            turns: Vec::new(),
+            score: None,
+            steps: None,
+        }
+    }
+
+    // Record a turn the board accepted before its next step
+    pub fn record_turn(&mut self, board: &GameBoard, direction: Direction) {
+        self.turns.push((board.steps(), direction));
+    }
+
+    // Record how far the game got, whether it's over or was left part way through
+    pub fn finish(&mut self, board: &GameBoard) {
+        self.score = Some(board.score());
+        self.steps = Some(board.steps());
+    }
+
+    // The board the game started with
+    pub fn new_board(&self) -> GameBoard {
+        GameBoard::with_seed(&self.level, self.seed).with_wrap(self.mode == "wrap")
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        Replay::parse(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let file: ReplayFile = toml::from_str(text).map_err(|err| err.to_string())?;
+        if file.version != REPLAY_VERSION {
+            return Err(format!(
+                "replay version {} can't be played by this version of the game",
+                file.version
+            ));
+        }
+        if !matches!(file.mode.as_str(), "classic" | "wrap") {
+            return Err(format!("there's no '{}' mode", file.mode));
+        }
+        let turns = file
+            .turns
+            .split_whitespace()
+            .map(|turn| parse_turn(turn).ok_or(format!("'{}' isn't a turn", turn)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut level = match file.level {
+            Some(map) => Level::parse(file.level_name.as_deref().unwrap_or(""), &map)?,
+            None => Level::empty(file.width, file.height),
+        };
+        if let Some(target) = file.target {
+            level = level.with_target(target);
+        }
+        if (level.columns(), level.rows()) != (file.width, file.height) {
+            return Err(format!(
+                "the level is {}x{} but the board is {}x{}",
+                level.columns(),
+                level.rows(),
+                file.width,
+                file.height
+            ));
+        }
+        let speed_curve = match (file.speed_curve, file.foods_per_level) {
+            (Some(multipliers), Some(foods_per_level)) => {
+                SpeedCurve::new(multipliers, foods_per_level)?
+            }
+            _ => SpeedCurve::constant(),
+        };
+        Ok(Replay {
+            seed: file.seed,
+            columns: file.width,
+            rows: file.height,
+            level,
+            speed: file.speed,
+            speed_curve,
+            mode: file.mode,
+            turns,
+            score: file.score,
+            steps: file.steps,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let error = |err: std::io::Error| format!("Couldn't save {}: {}", path.display(), err);
+        let text = self.to_toml()?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(error)?;
+        }
+        fs::write(path, text).map_err(error)
+    }
+
+    fn to_toml(&self) -> Result<String, String> {
+        let turns: Vec<String> = self
+            .turns
+            .iter()
+            .map(|(step, direction)| format!("{}{}", step, direction_letter(*direction)))
+            .collect();
+        let file = ReplayFile {
+            version: REPLAY_VERSION,
+            seed: self.seed,
+            width: self.columns,
+            height: self.rows,
+            level_name: Some(self.level.name().to_string()).filter(|name| !name.is_empty()),
+            level: Some(self.level.to_text())
+                .filter(|map| *map != Level::empty(self.columns, self.rows).to_text()),
+            target: self.level.target(),
+            speed: self.speed,
+            speed_curve: Some(self.speed_curve.multipliers().to_vec()),
+            foods_per_level: Some(self.speed_curve.foods_per_level()),
+            mode: self.mode.clone(),
+            score: self.score,
+            steps: self.steps,
+            turns: turns.join(" "),
+        };
+        toml::to_string(&file).map_err(|err| err.to_string())
+    }
+
+    // $XDG_DATA_HOME/rusty_snake/replays, falling back to ~/.local/share when it isn't set
+    pub fn default_directory() -> Option<PathBuf> {
+        Some(xdg_directory("XDG_DATA_HOME", ".local/share")?.join("replays"))
+    }
+
+    // Save the replay into `directory` named after when it was saved and its seed, then delete the
+    // oldest replays so there are at most MAX_REPLAYS. Returns where it was saved.
+    // Games with the same seed can be saved in the same second, by this copy of the game or
+    // another one, so the file is only ever created new. If the name is taken a number is added.
+    pub fn save_to_directory(&self, directory: &Path) -> Result<PathBuf, String> {
+        let error =
+            |err: std::io::Error| format!("Couldn't save in {}: {}", directory.display(), err);
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let text = self.to_toml()?;
+        fs::create_dir_all(directory).map_err(error)?;
+        let mut copy = 1;
+        let path = loop {
+            // Padded so the file names sort oldest first. Later copies sort after the first one.
+            let name = match copy {
+                1 => format!("{:012}-{}.toml", seconds, self.seed),
+                _ => format!("{:012}-{}_{}.toml", seconds, self.seed, copy),
+            };
+            let path = directory.join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(text.as_bytes()).map_err(error)?;
+                    break path;
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => copy += 1,
+                Err(err) => return Err(error(err)),
+            }
+        };
+
+        let mut replays: Vec<PathBuf> = fs::read_dir(directory)
+            .map_err(|err| format!("Couldn't read {}: {}", directory.display(), err))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "toml")
+            })
+            .collect();
+        replays.sort();
+        let excess = replays.len().saturating_sub(MAX_REPLAYS);
+        for old in &replays[..excess] {
+            // Another copy of the game may have already deleted it
+            let _ = fs::remove_file(old);
+        }
+        Ok(path)
+    }
+
+    // Play the whole game without drawing it and check it ends the way it did when it was
+    // recorded. Returns the board at the end.
+    pub fn verify(&self) -> Result<GameBoard, String> {
+        let mut player = ReplayPlayer::new(self.clone());
+        while !player.is_finished() {
+            player.step();
+        }
+        let board = player.board;
+        let expected = (self.score, self.steps);
+        let actual = (Some(board.score()), Some(board.steps()));
+        if expected.0.is_some() && expected != actual {
+            return Err(format!(
+                "expected a score of {} after {} steps but got {} after {}",
+                self.score.unwrap_or(0),
+                self.steps.unwrap_or(0),
+                board.score(),
+                board.steps()
+            ));
+        }
+        Ok(board)
+    }
+}
+
+// Drives a GameBoard from a Replay. It can step forwards and seek to any step, which replays the
+// game from the start since the board can't be stepped backwards.
+pub struct ReplayPlayer {
+    replay: Replay,
+    board: GameBoard,
+    // Index of the next turn in `replay.turns` to apply
+    next_turn: usize,
+    // Points scored so far, which depend on how fast the snake was going
+    points: u32,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        let board = replay.new_board();
+        ReplayPlayer {
+            replay,
+            board,
+            next_turn: 0,
+            points: 0,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn board(&self) -> &GameBoard {
+        &self.board
+    }
+
+    pub fn points(&self) -> u32 {
+        self.points
+    }
+
+    // Moves per second the game was going at when it got to this step
+    pub fn speed(&self) -> f64 {
+        self.replay
+            .speed_curve
+            .speed(self.replay.speed, self.board.score())
+    }
+
+    // The step the board is on
+    pub fn position(&self) -> u32 {
+        self.board.steps()
+    }
+
+    // How many steps the game lasts. Unfinished recordings last until their last turn.
+    pub fn length(&self) -> u32 {
+        self.replay
+            .steps
+            .unwrap_or_else(|| self.replay.turns.last().map_or(0, |(step, _)| step + 1))
+    }
+
+    // True once the game is over or the recording runs out
+    pub fn is_finished(&self) -> bool {
+        !self.board.is_active() || self.position() >= self.length()
+    }
+
+    // Apply the turns made before this step then step the board
+    pub fn step(&mut self) -> GameEvent {
+        if self.is_finished() {
+            return GameEvent::Inactive;
+        }
+        while let Some(&(step, direction)) = self.replay.turns.get(self.next_turn) {
+            if step > self.board.steps() {
+                break;
+            }
+            self.board.update_snake_direction(direction);
+            self.next_turn += 1;
+        }
+        let eaten = self.board.score();
+        let points = self
+            .replay
+            .speed_curve
+            .points_for_food(self.replay.speed, eaten);
+        let event = self.board.step(None);
+        if self.board.score() > eaten {
+            self.points += points;
+        }
+        event
+    }
+
+    // Move to `position`, going back to the start first if it's behind the current step
+    pub fn seek(&mut self, position: u32) {
+        if position < self.position() {
+            self.board = self.replay.new_board();
+            self.next_turn = 0;
+            self.points = 0;
+        }
+        while self.position() < position && !self.is_finished() {
+            self.step();
+        }
+    }
+}
@@ -0,0 +1,291 @@
+// Copyright (c) 2022 DanWillans
+use crate::campaign::Campaign;
+use crate::input::is_ctrl_c;
+use crate::play::ScoreBoard;
+use crate::{Config, DrawScreen, InputEvent};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+// The longest stall the game loop catches up on. After a longer one the game carries on from where
+// it was rather than running a burst of ticks the player can't react to.
+const MAX_CATCH_UP: Duration = Duration::from_millis(250);
+
+// Everything the scenes share. Scenes keep their own state and get this passed in.
+pub struct Context {
+    pub config: Config,
+    pub screen: DrawScreen,
+    // The high-score table is shown on the title screen and when a game ends
+    pub score_board: ScoreBoard,
+    // Frames drawn per second, measured by the game loop
+    pub fps: f32,
+    // What the first scene is called, like "title", for the menus that go back to it
+    pub home: &'static str,
+    // The levels being played when the game was started as a campaign
+    pub campaign: Option<Campaign>,
+}
+
+impl Context {
+    pub fn new(
+        config: Config,
+        screen: DrawScreen,
+        home: &'static str,
+        campaign: Option<Campaign>,
+    ) -> Self {
+        Context {
+            config,
+            screen,
+            score_board: ScoreBoard::load(),
+            fps: 0.0,
+            home,
+            campaign,
+        }
+    }
+}
+
+// What a scene wants to happen next
+pub enum Transition {
+    // Carry on with the same scene
+    Stay,
+    // Open a scene on top of this one, like a menu. This one keeps its state and comes back when
+    // the new one is closed.
+    Push(Box<dyn Scene>),
+    // Replace this scene with another
+    Switch(Box<dyn Scene>),
+    // Close this scene and go back to the one under it
+    Pop,
+    // Close every scene apart from the first and open this one on top of it, like starting a new
+    // game
+    Reset(Box<dyn Scene>),
+    // Close every scene apart from the first, like going back to the title screen
+    Home,
+    // Close every scene, which ends the game
+    Quit,
+}
+
+// One screen of the game, like the title screen, a game being played or a menu. Only the scene on
+// top of the SceneStack gets input and updates. The first scene, like the title screen or the level
+// editor, stays at the bottom of the stack until the game ends.
+pub trait Scene {
+    // Called when the scene is added to the stack
+    fn enter(&mut self, _context: &mut Context) {}
+
+    // Called when the scene is taken off the stack
+    fn exit(&mut self, _context: &mut Context) {}
+
+    // Called when the scenes on top of this one have closed and it's on top again
+    fn resume(&mut self, _context: &mut Context) {}
+
+    // Handle a key press, resize or focus change. The screen has already been resized.
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition;
+
+    // How many times a second `tick` is called while the scene is on top. None for scenes that
+    // only change when there's input, like menus.
+    fn tick_rate(&self, _context: &Context) -> Option<f64> {
+        None
+    }
+
+    // Advance the scene by one fixed step of 1 / tick_rate seconds. Ticks happen at a steady rate
+    // however often the screen is drawn, catching up after a stall.
+    fn tick(&mut self, _context: &mut Context) -> Transition {
+        Transition::Stay
+    }
+
+    // Called once a frame, just before drawing, while the scene is on top
+    fn update(&mut self, _context: &mut Context) -> Transition {
+        Transition::Stay
+    }
+
+    // Draw the scene into the screen's back buffer. The screen has been cleared first.
+    fn draw(&self, context: &mut Context);
+
+    // True if the scene only covers part of the screen, like a menu. The closest scene under it
+    // that isn't an overlay is drawn first, so menus opened from menus replace each other.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+// The scenes that are open, with the one the player is using on top
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+}
+
+impl SceneStack {
+    pub fn new(mut first: Box<dyn Scene>, context: &mut Context) -> Self {
+        first.enter(context);
+        SceneStack {
+            scenes: vec![first],
+        }
+    }
+
+    // True once the last scene has closed
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    // Pass input to the scene on top. Resizes are applied to the screen first and Ctrl+C closes
+    // everything straight away.
+    pub fn handle_input(&mut self, input: &InputEvent, context: &mut Context) {
+        match input {
+            InputEvent::Key(key) if is_ctrl_c(key) => {
+                self.apply(Transition::Quit, context);
+                return;
+            }
+            InputEvent::Resize(width, height) => context.screen.resize(*width, *height),
+            _ => {}
+        }
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.handle_input(input, context);
+            self.apply(transition, context);
+        }
+    }
+
+    pub fn update(&mut self, context: &mut Context) {
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.update(context);
+            self.apply(transition, context);
+        }
+    }
+
+    // Time between ticks of the scene on top, if it ticks
+    fn tick_time(&self, context: &Context) -> Option<Duration> {
+        let rate = self.scenes.last()?.tick_rate(context)?;
+        Some(Duration::from_secs_f64(1.0 / rate))
+    }
+
+    fn tick(&mut self, context: &mut Context) {
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.tick(context);
+            self.apply(transition, context);
+        }
+    }
+
+    // Run the scenes until the last one closes, `stop` is set or the input thread stops.
+    // Time is added to an accumulator which is spent on whole ticks of the scene on top, so the
+    // game runs at the same speed whatever the frame rate. The screen is drawn config.frame_rate
+    // times a second. In between, the loop waits on `input` so key presses are handled as soon as
+    // they arrive and apply to the very next tick.
+    pub fn run(&mut self, context: &mut Context, input: &Receiver<InputEvent>, stop: &AtomicBool) {
+        let mut accumulator = Duration::ZERO;
+        let mut last_time = Instant::now();
+        let mut last_frame = Instant::now();
+        let mut next_frame = Instant::now();
+        while !self.is_empty() && !stop.load(Ordering::Relaxed) {
+            let now = Instant::now();
+            match self.tick_time(context) {
+                Some(tick_time) => {
+                    accumulator =
+                        (accumulator + (now - last_time)).min(MAX_CATCH_UP.max(tick_time));
+                    // Tick until the accumulator runs out or a tick opens a scene that doesn't
+                    // tick at the same rate, like the game over screen
+                    while accumulator >= tick_time && self.tick_time(context) == Some(tick_time) {
+                        self.tick(context);
+                        accumulator -= tick_time;
+                    }
+                }
+                // Time spent in a menu doesn't count towards the next tick
+                None => accumulator = Duration::ZERO,
+            }
+            last_time = now;
+
+            if now >= next_frame {
+                self.update(context);
+                self.draw(context);
+                context.screen.draw();
+                context.fps = 1.0 / (now - last_frame).as_secs_f32().max(f32::EPSILON);
+                last_frame = now;
+                // Skip frames that were missed rather than drawing them all at once
+                let frame_time = Duration::from_secs_f64(1.0 / context.config.frame_rate);
+                next_frame += frame_time;
+                if next_frame < now {
+                    next_frame = now + frame_time;
+                }
+            }
+
+            // Wait until the next tick or frame is due, handling any input that arrives first
+            let next_tick = self
+                .tick_time(context)
+                .map(|tick_time| now + tick_time.saturating_sub(accumulator));
+            let deadline = next_tick.map_or(next_frame, |next_tick| next_tick.min(next_frame));
+            match input.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(event) => {
+                    self.handle_input(&event, context);
+                    for event in input.try_iter() {
+                        self.handle_input(&event, context);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        // Close whatever is still open so scenes can save what they need to, like a replay
+        self.apply(Transition::Quit, context);
+    }
+
+    // Draw the top scene, along with the scene under it if it's an overlay
+    pub fn draw(&self, context: &mut Context) {
+        context.screen.clear();
+        let Some(top) = self.scenes.last() else {
+            return;
+        };
+        if top.is_overlay() {
+            if let Some(base) = self.scenes.iter().rev().find(|scene| !scene.is_overlay()) {
+                base.draw(context);
+            }
+        }
+        top.draw(context);
+    }
+
+    fn pop(&mut self, context: &mut Context) {
+        if let Some(mut scene) = self.scenes.pop() {
+            scene.exit(context);
+        }
+    }
+
+    fn push(&mut self, mut scene: Box<dyn Scene>, context: &mut Context) {
+        scene.enter(context);
+        self.scenes.push(scene);
+    }
+
+    // Close every scene apart from the first
+    fn pop_to_first(&mut self, context: &mut Context) {
+        while self.scenes.len() > 1 {
+            self.pop(context);
+        }
+    }
+
+    fn resume(&mut self, context: &mut Context) {
+        if let Some(scene) = self.scenes.last_mut() {
+            scene.resume(context);
+        }
+    }
+
+    fn apply(&mut self, transition: Transition, context: &mut Context) {
+        match transition {
+            Transition::Stay => {}
+            Transition::Push(scene) => self.push(scene, context),
+            Transition::Switch(scene) => {
+                self.pop(context);
+                self.push(scene, context);
+            }
+            Transition::Pop => {
+                self.pop(context);
+                self.resume(context);
+            }
+            Transition::Reset(scene) => {
+                self.pop_to_first(context);
+                self.push(scene, context);
+            }
+            Transition::Home => {
+                self.pop_to_first(context);
+                self.resume(context);
+            }
+            Transition::Quit => {
+                while !self.scenes.is_empty() {
+                    self.pop(context);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2022 DanWillans
+use crate::config::xdg_directory;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// How many scores the table keeps
+pub const MAX_HIGH_SCORES: usize = 10;
+
+// The longest name that can be entered
+pub const MAX_NAME_LENGTH: usize = 12;
+
+// One finished game in the high-score table
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: u32,
+    // Length of the snake at the end of the game
+    pub length: usize,
+    // How long the game lasted in seconds of play, not counting pauses
+    pub duration: u32,
+    #[serde(with = "crate::config::toml_seed")]
+    pub seed: u64,
+    // The kind of game that was played, like "classic"
+    pub mode: String,
+    // The day the game was played as YYYY-MM-DD
+    pub date: String,
+}
+
+// The best scores, highest first. Games with the same score keep the order they were played in.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(default, rename = "score")]
+    entries: Vec<ScoreEntry>,
+}
+
+impl HighScores {
+    // $XDG_DATA_HOME/rusty_snake/scores.toml, falling back to ~/.local/share when it isn't set
+    pub fn default_path() -> Option<PathBuf> {
+        Some(xdg_directory("XDG_DATA_HOME", ".local/share")?.join("scores.toml"))
+    }
+
+    // Read the table from `path`. A missing file is an empty table.
+    pub fn load(path: &Path) -> Result<HighScores, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(HighScores::default()),
+            Err(err) => return Err(format!("Couldn't read {}: {}", path.display(), err)),
+        };
+        let mut scores: HighScores =
+            toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        scores.sort();
+        Ok(scores)
+    }
+
+    pub fn entries(&self) -> &[ScoreEntry] {
+        &self.entries
+    }
+
+    // True if a game with this score would make it into the table
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && match self.entries.get(MAX_HIGH_SCORES - 1) {
+                // A full table needs a better score than the last one
+                Some(last) => score > last.score,
+                None => true,
+            }
+    }
+
+    fn sort(&mut self) {
+        // A stable sort so earlier games stay ahead of later ones with the same score
+        self.entries
+            .sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        self.entries.truncate(MAX_HIGH_SCORES);
+    }
+
+    // Add the entry to the table, returning its index or None if it didn't make it in
+    fn insert(&mut self, entry: ScoreEntry) -> Option<usize> {
+        let index = self
+            .entries
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or(self.entries.len());
+        if index >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.entries.insert(index, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(index)
+    }
+
+    // Add a score to the table saved at `path` and return the updated table along with where the
+    // score ended up in it.
+    // Other copies of the game may be saving at the same time, so the table is re-read under a
+    // lock and the new file is written alongside the old one then renamed over it. Readers see
+    // either the old table or the new one and never half of a file.
+    pub fn record(path: &Path, entry: ScoreEntry) -> Result<(HighScores, Option<usize>), String> {
+        let error = |err: std::io::Error| format!("Couldn't save {}: {}", path.display(), err);
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(error)?;
+        }
+        let lock = File::create(path.with_extension("lock")).map_err(error)?;
+        lock.lock_exclusive().map_err(error)?;
+
+        let mut scores = HighScores::load(path)?;
+        let index = scores.insert(entry);
+        let text = toml::to_string(&scores).map_err(|err| err.to_string())?;
+        let temporary = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&temporary, text).map_err(error)?;
+        fs::rename(&temporary, path).map_err(error)?;
+
+        // Dropping the file releases the lock but unlocking first reports any problem
+        lock.unlock().map_err(error)?;
+        Ok((scores, index))
+    }
+}
+
+// Today's date in UTC as YYYY-MM-DD
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Turn a count of days since 1970-01-01 into a year, month and day in the Gregorian calendar.
+// From http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// Format a number of seconds as minutes and seconds like 3:07
+pub fn format_duration(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u32) -> ScoreEntry {
+        ScoreEntry {
+            name: name.to_string(),
+            score,
+            length: 3,
+            duration: 60,
+            seed: 1,
+            mode: "classic".to_string(),
+            date: "2022-01-01".to_string(),
+        }
+    }
+
+    fn names(scores: &HighScores) -> Vec<&str> {
+        scores
+            .entries()
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    // A full table scoring 100, 90 and so on down to 10
+    fn full_table() -> HighScores {
+        let mut scores = HighScores::default();
+        for score in (1..=MAX_HIGH_SCORES as u32).rev() {
+            scores.insert(entry(&score.to_string(), score * 10));
+        }
+        scores
+    }
+
+    #[test]
+    fn any_score_qualifies_until_the_table_is_full() {
+        let mut scores = HighScores::default();
+        assert!(!scores.qualifies(0));
+        assert!(scores.qualifies(1));
+        scores.insert(entry("a", 50));
+        assert!(scores.qualifies(1));
+        let scores = full_table();
+        assert!(!scores.qualifies(9));
+        assert!(!scores.qualifies(10));
+        assert!(scores.qualifies(11));
+    }
+
+    #[test]
+    fn inserts_in_score_order() {
+        let mut scores = HighScores::default();
+        assert_eq!(scores.insert(entry("b", 20)), Some(0));
+        assert_eq!(scores.insert(entry("a", 30)), Some(0));
+        assert_eq!(scores.insert(entry("c", 10)), Some(2));
+        assert_eq!(names(&scores), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn ties_go_after_the_games_already_in_the_table() {
+        let mut scores = HighScores::default();
+        scores.insert(entry("first", 20));
+        scores.insert(entry("low", 10));
+        assert_eq!(scores.insert(entry("second", 20)), Some(1));
+        assert_eq!(names(&scores), ["first", "second", "low"]);
+    }
+
+    #[test]
+    fn full_table_drops_the_lowest_score() {
+        let mut scores = full_table();
+        assert_eq!(scores.insert(entry("new", 55)), Some(5));
+        assert_eq!(scores.entries().len(), MAX_HIGH_SCORES);
+        assert_eq!(scores.entries()[5].name, "new");
+        assert_eq!(scores.entries().last().unwrap().score, 20);
+        // Not beating the last score leaves the table alone
+        assert_eq!(scores.insert(entry("tie", 20)), None);
+        assert_eq!(scores.entries().last().unwrap().name, "2");
+    }
+
+    #[test]
+    fn loading_sorts_and_trims_the_table() {
+        let mut scores = full_table();
+        scores.entries.reverse();
+        scores.entries.push(entry("extra", 5));
+        let path =
+            std::env::temp_dir().join(format!("rusty_snake_scores_{}.toml", std::process::id()));
+        fs::write(&path, toml::to_string(&scores).unwrap()).unwrap();
+        assert_eq!(HighScores::load(&path).unwrap(), full_table());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn days_since_1970_as_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(20_088), (2024, 12, 31));
+    }
+
+    #[test]
+    fn century_leap_years() {
+        // 2000 divides by 400 so it's a leap year, 2100 only divides by 100 so it isn't
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(civil_from_days(47_540), (2100, 2, 28));
+        assert_eq!(civil_from_days(47_541), (2100, 3, 1));
+    }
+}
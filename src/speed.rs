@@ -0,0 +1,120 @@
+// Copyright (c) 2022 DanWillans
+
+// How the snake speeds up as it eats. Every `foods_per_level` pieces of food the player goes up a
+// level and the speed becomes the base speed times the next multiplier in the curve. After the
+// last multiplier the speed stays the same. A curve of just [1.0] keeps the speed constant.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeedCurve {
+    multipliers: Vec<f64>,
+    foods_per_level: u16,
+}
+
+impl SpeedCurve {
+    pub fn new(multipliers: Vec<f64>, foods_per_level: u16) -> Result<Self, String> {
+        if multipliers.is_empty() {
+            return Err("speed_curve needs at least one multiplier".to_string());
+        }
+        if let Some(bad) = multipliers.iter().find(|m| !m.is_finite() || **m <= 0.0) {
+            return Err(format!(
+                "speed_curve multipliers have to be more than 0, not {}",
+                bad
+            ));
+        }
+        if foods_per_level == 0 {
+            return Err("foods_per_level has to be at least 1".to_string());
+        }
+        Ok(SpeedCurve {
+            multipliers,
+            foods_per_level,
+        })
+    }
+
+    // A curve that never speeds up
+    pub fn constant() -> Self {
+        SpeedCurve {
+            multipliers: vec![1.0],
+            foods_per_level: 1,
+        }
+    }
+
+    pub fn multipliers(&self) -> &[f64] {
+        &self.multipliers
+    }
+
+    pub fn foods_per_level(&self) -> u16 {
+        self.foods_per_level
+    }
+
+    // The level after eating `eaten` pieces of food, starting from 1
+    pub fn level(&self, eaten: u32) -> u32 {
+        let level = eaten / self.foods_per_level as u32;
+        level.min(self.multipliers.len() as u32 - 1) + 1
+    }
+
+    // Moves per second after eating `eaten` pieces of food, for a game started at `base_speed`
+    pub fn speed(&self, base_speed: f64, eaten: u32) -> f64 {
+        base_speed * self.multipliers[self.level(eaten) as usize - 1]
+    }
+
+    // Points for the next piece of food after eating `eaten`. Food is worth the speed the snake
+    // is moving at, so playing faster scores more.
+    pub fn points_for_food(&self, base_speed: f64, eaten: u32) -> u32 {
+        (self.speed(base_speed, eaten).round() as u32).max(1)
+    }
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        SpeedCurve {
+            multipliers: vec![1.0, 1.15, 1.3, 1.5, 1.7, 1.9, 2.1, 2.4, 2.7, 3.0],
+            foods_per_level: 5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> SpeedCurve {
+        SpeedCurve::new(vec![1.0, 1.5, 2.0], 5).unwrap()
+    }
+
+    #[test]
+    fn goes_up_a_level_every_foods_per_level() {
+        let curve = curve();
+        let levels: Vec<u32> = [0, 4, 5, 9, 10].map(|eaten| curve.level(eaten)).into();
+        assert_eq!(levels, [1, 1, 2, 2, 3]);
+        assert_eq!(curve.speed(10.0, 4), 10.0);
+        assert_eq!(curve.speed(10.0, 5), 15.0);
+    }
+
+    #[test]
+    fn stops_speeding_up_after_the_last_multiplier() {
+        let curve = curve();
+        assert_eq!(curve.level(15), 3);
+        assert_eq!(curve.level(u32::MAX), 3);
+        assert_eq!(curve.speed(10.0, u32::MAX), 20.0);
+        assert_eq!(SpeedCurve::constant().level(1000), 1);
+        assert_eq!(SpeedCurve::constant().speed(7.5, 1000), 7.5);
+    }
+
+    #[test]
+    fn food_is_worth_the_speed_rounded() {
+        let curve = curve();
+        assert_eq!(curve.points_for_food(10.0, 0), 10);
+        assert_eq!(curve.points_for_food(10.0, 5), 15);
+        assert_eq!(curve.points_for_food(3.0, 5), 5);
+        assert_eq!(curve.points_for_food(1.4, 0), 1);
+        // Food is always worth something however slow the game is
+        assert_eq!(curve.points_for_food(0.2, 0), 1);
+    }
+
+    #[test]
+    fn rejects_curves_that_cant_be_played() {
+        assert!(SpeedCurve::new(vec![], 5).is_err());
+        assert!(SpeedCurve::new(vec![1.0, 0.0], 5).is_err());
+        assert!(SpeedCurve::new(vec![1.0, f64::NAN], 5).is_err());
+        assert!(SpeedCurve::new(vec![1.0], 0).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (c) 2022 DanWillans
+
+// The glyphs used to draw the snake, food, walls and the exit. Each glyph fills one GameBoard cell
+// which is `cell_width` terminal columns wide, so the board has more columns when the glyphs are
+// narrow.
+// `ascii_borders` swaps the box drawing characters used for borders and the title for plain ASCII.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlyphTheme {
+    pub name: &'static str,
+    pub head: &'static str,
+    pub body: &'static str,
+    pub food: &'static str,
+    pub wall: &'static str,
+    pub exit: &'static str,
+    pub cell_width: u16,
+    pub ascii_borders: bool,
+}
+
+impl GlyphTheme {
+    // Emoji need a font and terminal that draw them two columns wide
+    pub const EMOJI: GlyphTheme = GlyphTheme {
+        name: "emoji",
+        head: "🙂",
+        body: "🐍",
+        food: "🍎",
+        wall: "🧱",
+        exit: "🚪",
+        cell_width: 2,
+        ascii_borders: false,
+    };
+
+    // Shapes and blocks that most fonts with box drawing characters also have. They're all neutral
+    // width characters, as ones like ● and █ are drawn two columns wide by CJK terminals.
+    pub const UNICODE: GlyphTheme = GlyphTheme {
+        name: "unicode",
+        head: "◉",
+        body: "⬢",
+        food: "⬥",
+        wall: "░",
+        exit: "⌂",
+        cell_width: 1,
+        ascii_borders: false,
+    };
+
+    // Works everywhere, including the Linux console
+    pub const ASCII: GlyphTheme = GlyphTheme {
+        name: "ascii",
+        head: "@",
+        body: "o",
+        food: "*",
+        wall: "#",
+        exit: "E",
+        cell_width: 1,
+        ascii_borders: true,
+    };
+
+    pub const ALL: [GlyphTheme; 3] = [GlyphTheme::EMOJI, GlyphTheme::UNICODE, GlyphTheme::ASCII];
+
+    // Look up a built in theme by name
+    pub fn by_name(name: &str) -> Option<GlyphTheme> {
+        GlyphTheme::ALL
+            .into_iter()
+            .find(|theme| theme.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Default for GlyphTheme {
+    fn default() -> Self {
+        GlyphTheme::EMOJI
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (c) 2022 DanWillans
+use crate::play::{needed_screen_size, new_board_view, PlayingScene};
+use crate::scene::{Context, Scene, Transition};
+use crate::view::{draw_high_score_screen, draw_screen_too_small, title_position};
+use crate::{draw_title_screen, Command, InputEvent};
+use std::time::{Duration, Instant};
+
+// How long the title and the high-score table each stay on screen before swapping
+const TITLE_PAGE_TIME: Duration = Duration::from_secs(5);
+
+// The title screen. The title and the high-score table take turns on screen until the space bar
+// starts a game.
+pub struct TitleScene {
+    showing_scores: bool,
+    last_switch: Instant,
+}
+
+impl TitleScene {
+    pub fn new() -> Self {
+        TitleScene {
+            showing_scores: false,
+            last_switch: Instant::now(),
+        }
+    }
+}
+
+impl Default for TitleScene {
+    fn default() -> Self {
+        TitleScene::new()
+    }
+}
+
+impl Scene for TitleScene {
+    // Always start on the title rather than the table
+    fn enter(&mut self, _context: &mut Context) {
+        self.showing_scores = false;
+        self.last_switch = Instant::now();
+    }
+
+    // Back from a game
+    fn resume(&mut self, context: &mut Context) {
+        self.enter(context);
+    }
+
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let InputEvent::Key(key) = input else {
+            return Transition::Stay;
+        };
+        match context.config.keys.command_for_key(key) {
+            Some(Command::Start) => match PlayingScene::new(context) {
+                Some(playing) => Transition::Push(Box::new(playing)),
+                None => Transition::Stay,
+            },
+            // Esc pauses in a game, here there's nothing to pause so it quits
+            Some(Command::Quit | Command::Pause) => Transition::Quit,
+            _ => Transition::Stay,
+        }
+    }
+
+    fn update(&mut self, context: &mut Context) -> Transition {
+        let has_scores = !context.score_board.scores().entries().is_empty();
+        if self.last_switch.elapsed() >= TITLE_PAGE_TIME && has_scores {
+            self.showing_scores = !self.showing_scores;
+            self.last_switch = Instant::now();
+        }
+        Transition::Stay
+    }
+
+    // Draw the title screen or the high-score table. Asks for a bigger terminal if the game
+    // doesn't fit.
+    fn draw(&self, context: &mut Context) {
+        let screen = &mut context.screen;
+        if new_board_view(&context.config, screen).is_none() {
+            draw_screen_too_small(needed_screen_size(&context.config), screen);
+        } else if self.showing_scores {
+            draw_high_score_screen(context.score_board.scores(), screen);
+        } else {
+            draw_title_screen(title_position(screen), screen);
+        }
+    }
+}
@@ -0,0 +1,719 @@
+// Copyright (c) 2022 DanWillans
+use crate::game::{GameBoard, SnakeElement};
+use crate::menu::Menu;
+use crate::rendering::text_width;
+use crate::scores::{format_duration, HighScores, MAX_NAME_LENGTH};
+use crate::theme::GlyphTheme;
+use crate::{DrawColor, DrawScreen, Level};
+
+// The smallest terminal the game can be played in
+pub const MIN_SCREEN_WIDTH: u16 = 80;
+pub const MIN_SCREEN_HEIGHT: u16 = 20;
+// Space kept either side of the board for the score and fps counter
+const SIDE_MARGIN: u16 = 14;
+// Size of the title drawn by draw_title_screen
+const TITLE_WIDTH: u16 = 75;
+const TITLE_HEIGHT: u16 = 16;
+
+// Returns true if the screen is big enough to play in
+pub fn screen_fits(screen: &DrawScreen) -> bool {
+    screen.width() >= MIN_SCREEN_WIDTH && screen.height() >= MIN_SCREEN_HEIGHT
+}
+
+// Where to draw the title so it's centred on the screen
+pub fn title_position(screen: &DrawScreen) -> (u16, u16) {
+    (
+        screen.width().saturating_sub(TITLE_WIDTH) / 2,
+        screen.height().saturating_sub(TITLE_HEIGHT) / 2,
+    )
+}
+
+// Ask the player to make the terminal at least `needed` columns and rows
+pub fn draw_screen_too_small(needed: (u16, u16), screen: &mut DrawScreen) {
+    let lines = [
+        "Terminal too small!".to_string(),
+        format!("Need {}x{}", needed.0, needed.1),
+        format!("Have {}x{}", screen.width(), screen.height()),
+    ];
+    let top = (screen.height() / 2).saturating_sub(1);
+    for (i, line) in lines.into_iter().enumerate() {
+        let x = screen.width().saturating_sub(text_width(&line)) / 2;
+        screen.update_with_string(x, top + i as u16, line, DrawColor::Red);
+    }
+}
+
+// One line per high score, like " 1. Dan            42    1:07  2022-10-18"
+pub fn high_score_lines(scores: &HighScores) -> Vec<String> {
+    scores
+        .entries()
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            format!(
+                "{:>2}. {:<12} {:>5}  {:>6}  {}",
+                index + 1,
+                entry.name,
+                entry.score,
+                format_duration(entry.duration),
+                entry.date
+            )
+        })
+        .collect()
+}
+
+// Draw a menu in a box in the middle of the screen, over whatever is already there. The selected
+// item is marked with a >.
+pub fn draw_menu<T: Copy + PartialEq>(menu: &Menu<T>, screen: &mut DrawScreen) {
+    let labels: Vec<&str> = menu.labels().collect();
+    let inner_width = labels
+        .iter()
+        .map(|label| text_width(label) + 2)
+        .chain([text_width(menu.title())])
+        .max()
+        .unwrap_or(0)
+        + 4;
+    let width = inner_width + 2;
+    let height = labels.len() as u16 + 4;
+    let x = screen.width().saturating_sub(width) / 2;
+    let y = screen.height().saturating_sub(height) / 2;
+
+    // Box drawing characters are swapped for ASCII by the DrawScreen when it needs to
+    let right = x + width - 1;
+    let bottom = y + height - 1;
+    for column in x + 1..right {
+        screen.update(column, y, '─', DrawColor::GameBorder);
+        screen.update(column, bottom, '─', DrawColor::GameBorder);
+    }
+    let blank = " ".repeat(inner_width as usize);
+    for row in y + 1..bottom {
+        screen.update(x, row, '│', DrawColor::GameBorder);
+        screen.update_with_string(x + 1, row, blank.clone(), DrawColor::White);
+        screen.update(right, row, '│', DrawColor::GameBorder);
+    }
+    screen.update(x, y, '┌', DrawColor::GameBorder);
+    screen.update(right, y, '┐', DrawColor::GameBorder);
+    screen.update(x, bottom, '└', DrawColor::GameBorder);
+    screen.update(right, bottom, '┘', DrawColor::GameBorder);
+
+    let title_x = x + (width - text_width(menu.title())) / 2;
+    screen.update_with_string(
+        title_x,
+        y + 1,
+        menu.title().to_string(),
+        DrawColor::GameBorder,
+    );
+    for (i, label) in labels.into_iter().enumerate() {
+        let (marker, color) = if i == menu.selected_index() {
+            ('>', DrawColor::Green)
+        } else {
+            (' ', DrawColor::White)
+        };
+        screen.update_with_string(
+            x + 3,
+            y + 3 + i as u16,
+            format!("{} {}", marker, label),
+            color,
+        );
+    }
+}
+
+// Draw the high-score table in the middle of the screen in place of the title
+pub fn draw_high_score_screen(scores: &HighScores, screen: &mut DrawScreen) {
+    let lines = high_score_lines(scores);
+    let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+    let x = screen.width().saturating_sub(width) / 2;
+    let top = screen.height().saturating_sub(lines.len() as u16 + 2) / 2;
+    let heading = "HIGH SCORES".to_string();
+    screen.update_with_string(
+        screen.width().saturating_sub(text_width(&heading)) / 2,
+        top,
+        heading,
+        DrawColor::GameBorder,
+    );
+    for (i, line) in lines.into_iter().enumerate() {
+        screen.update_with_string(x, top + 2 + i as u16, line, DrawColor::White);
+    }
+}
+
+pub fn draw_title_screen(position: (u16, u16), screen: &mut DrawScreen) {
+    screen.update_with_string(
+        position.0 + 17,
+        position.1 + 15,
+        "Press the space bar to play or Q to quit.".to_string(),
+        DrawColor::White,
+    );
+    let r_datum = position;
+    screen.update(r_datum.0, r_datum.1, '╔', DrawColor::GameBorder);
+    screen.update(r_datum.0, r_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(r_datum.0, r_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(r_datum.0, r_datum.1 + 3, '╠', DrawColor::GameBorder);
+    screen.update(r_datum.0, r_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(r_datum.0, r_datum.1 + 5, '╙', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 1, r_datum.1, '═', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 2, r_datum.1, '═', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 3, r_datum.1, '═', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 4, r_datum.1, '═', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 5, r_datum.1, '╗', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 5, r_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 5, r_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 5, r_datum.1 + 3, '╣', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 5, r_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 5, r_datum.1 + 5, '╙', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 1, r_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 2, r_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 3, r_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(r_datum.0 + 4, r_datum.1 + 3, '═', DrawColor::GameBorder);
+    let u_datum = (r_datum.0 + 7, r_datum.1);
+    screen.update(u_datum.0, u_datum.1, '╓', DrawColor::GameBorder);
+    screen.update(u_datum.0, u_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(u_datum.0, u_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(u_datum.0, u_datum.1 + 3, '║', DrawColor::GameBorder);
+    screen.update(u_datum.0, u_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(u_datum.0, u_datum.1 + 5, '╚', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 1, u_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 2, u_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 3, u_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 4, u_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 5, u_datum.1 + 5, '╝', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 5, u_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 5, u_datum.1 + 3, '║', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 5, u_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 5, u_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(u_datum.0 + 5, u_datum.1, '╖', DrawColor::GameBorder);
+    let s_datum = (u_datum.0 + 7, u_datum.1);
+    screen.update(s_datum.0 + 5, s_datum.1, '╕', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 4, s_datum.1, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 3, s_datum.1, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 2, s_datum.1, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 1, s_datum.1, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0, s_datum.1, '╔', DrawColor::GameBorder);
+    screen.update(s_datum.0, s_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(s_datum.0, s_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(s_datum.0, s_datum.1 + 3, '╚', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 1, s_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 2, s_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 3, s_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 4, s_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 5, s_datum.1 + 3, '╗', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 5, s_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 5, s_datum.1 + 5, '║', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 5, s_datum.1 + 5, '╝', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 4, s_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 3, s_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 2, s_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0 + 1, s_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(s_datum.0, s_datum.1 + 5, '╘', DrawColor::GameBorder);
+    let t_datum = (s_datum.0 + 7, s_datum.1);
+    screen.update(t_datum.0, t_datum.1, '╒', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 1, t_datum.1, '═', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 2, t_datum.1, '═', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 3, t_datum.1, '╦', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 4, t_datum.1, '═', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 5, t_datum.1, '═', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 6, t_datum.1, '╕', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 3, t_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 3, t_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 3, t_datum.1 + 3, '║', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 3, t_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(t_datum.0 + 3, t_datum.1 + 5, '╨', DrawColor::GameBorder);
+    let y_datum = (t_datum.0 + 8, t_datum.1);
+    screen.update(y_datum.0, y_datum.1, '╓', DrawColor::GameBorder);
+    screen.update(y_datum.0, y_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(y_datum.0, y_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(y_datum.0, y_datum.1 + 3, '╚', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 1, y_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 2, y_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 3, y_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 4, y_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 5, y_datum.1 + 3, '╣', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 5, y_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 5, y_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 5, y_datum.1, '╖', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 5, y_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(y_datum.0 + 5, y_datum.1 + 5, '╨', DrawColor::GameBorder);
+    let sn_datum = (y_datum.0 + 12, y_datum.1);
+    screen.update(sn_datum.0 + 5, sn_datum.1, '╕', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 4, sn_datum.1, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 3, sn_datum.1, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 2, sn_datum.1, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 1, sn_datum.1, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0, sn_datum.1, '╔', DrawColor::GameBorder);
+    screen.update(sn_datum.0, sn_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(sn_datum.0, sn_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(sn_datum.0, sn_datum.1 + 3, '╚', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 1, sn_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 2, sn_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 3, sn_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 4, sn_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 5, sn_datum.1 + 3, '╗', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 5, sn_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 5, sn_datum.1 + 5, '║', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 5, sn_datum.1 + 5, '╝', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 4, sn_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 3, sn_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 2, sn_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0 + 1, sn_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(sn_datum.0, sn_datum.1 + 5, '╘', DrawColor::GameBorder);
+    let n_datum = (sn_datum.0 + 7, sn_datum.1);
+    screen.update(n_datum.0, n_datum.1, '╔', DrawColor::GameBorder);
+    screen.update(n_datum.0, n_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(n_datum.0, n_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(n_datum.0, n_datum.1 + 3, '║', DrawColor::GameBorder);
+    screen.update(n_datum.0, n_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(n_datum.0, n_datum.1 + 5, '╙', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 1, n_datum.1, '═', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 2, n_datum.1, '═', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 3, n_datum.1, '═', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 4, n_datum.1, '═', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 5, n_datum.1, '╗', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 5, n_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 5, n_datum.1 + 3, '║', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 5, n_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 5, n_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(n_datum.0 + 5, n_datum.1 + 5, '╙', DrawColor::GameBorder);
+    let a_datum = (n_datum.0 + 7, n_datum.1);
+    screen.update(a_datum.0, a_datum.1, '╔', DrawColor::GameBorder);
+    screen.update(a_datum.0, a_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(a_datum.0, a_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(a_datum.0, a_datum.1 + 3, '╠', DrawColor::GameBorder);
+    screen.update(a_datum.0, a_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(a_datum.0, a_datum.1 + 5, '╙', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 1, a_datum.1, '═', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 2, a_datum.1, '═', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 3, a_datum.1, '═', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 4, a_datum.1, '═', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 5, a_datum.1, '╗', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 5, a_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 5, a_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 5, a_datum.1 + 3, '╣', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 5, a_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 5, a_datum.1 + 5, '╜', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 1, a_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 2, a_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 3, a_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(a_datum.0 + 4, a_datum.1 + 3, '═', DrawColor::GameBorder);
+    let k_datum = (a_datum.0 + 7, a_datum.1);
+    screen.update(k_datum.0, k_datum.1, '╓', DrawColor::GameBorder);
+    screen.update(k_datum.0, k_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(k_datum.0, k_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(k_datum.0, k_datum.1 + 3, '╠', DrawColor::GameBorder);
+    screen.update(k_datum.0, k_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(k_datum.0, k_datum.1 + 5, '╙', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 5, k_datum.1, '╓', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 5, k_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 5, k_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 5, k_datum.1 + 3, '╣', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 5, k_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 5, k_datum.1 + 5, '╙', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 1, k_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 2, k_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 3, k_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(k_datum.0 + 4, k_datum.1 + 3, '═', DrawColor::GameBorder);
+    let e_datum = (k_datum.0 + 7, k_datum.1);
+    screen.update(e_datum.0, e_datum.1, '╔', DrawColor::GameBorder);
+    screen.update(e_datum.0, e_datum.1 + 1, '║', DrawColor::GameBorder);
+    screen.update(e_datum.0, e_datum.1 + 2, '║', DrawColor::GameBorder);
+    screen.update(e_datum.0, e_datum.1 + 3, '╠', DrawColor::GameBorder);
+    screen.update(e_datum.0, e_datum.1 + 4, '║', DrawColor::GameBorder);
+    screen.update(e_datum.0, e_datum.1 + 5, '╙', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 1, e_datum.1, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 2, e_datum.1, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 3, e_datum.1, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 4, e_datum.1, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 5, e_datum.1, '╕', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 5, e_datum.1 + 3, '╡', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 5, e_datum.1 + 5, '╛', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 1, e_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 2, e_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 3, e_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 4, e_datum.1 + 3, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 1, e_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 2, e_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 3, e_datum.1 + 5, '═', DrawColor::GameBorder);
+    screen.update(e_datum.0 + 4, e_datum.1 + 5, '═', DrawColor::GameBorder);
+}
+
+// The BoardView draws a GameBoard onto the DrawScreen. `position`, `width` and `height` describe
+// the bordered panel in DrawScreen co-ordinates.
+// Each GameBoard cell is drawn with a glyph from the theme that's `cell_width` columns wide. With
+// emoji that's two columns so the GameBoard is half the resolution in the x direction compared to
+// the panel. The drawing functions correct GameBoard positions relative to the DrawScreen.
+pub struct BoardView {
+    position: (u16, u16),
+    width: u16,
+    height: u16,
+    theme: GlyphTheme,
+}
+
+impl BoardView {
+    pub fn new(position: (u16, u16), width: u16, height: u16, theme: GlyphTheme) -> Self {
+        BoardView {
+            position,
+            width,
+            height,
+            theme,
+        }
+    }
+
+    // The largest board that fits on the screen. None if the screen is too small to play in.
+    pub fn fill_screen(theme: GlyphTheme, screen: &DrawScreen) -> Option<Self> {
+        if !screen_fits(screen) {
+            return None;
+        }
+        let columns = (screen.width() - 2 * SIDE_MARGIN - 2) / theme.cell_width;
+        let rows = screen.height() - 4;
+        BoardView::fit_board(columns, rows, theme, screen)
+    }
+
+    // The smallest screen a board of `columns` by `rows` fits on
+    pub fn screen_size_for(columns: u16, rows: u16, theme: GlyphTheme) -> (u16, u16) {
+        let width = columns.saturating_mul(theme.cell_width).saturating_add(2);
+        (
+            MIN_SCREEN_WIDTH.max(width.saturating_add(2 * SIDE_MARGIN)),
+            MIN_SCREEN_HEIGHT.max(rows.saturating_add(4)),
+        )
+    }
+
+    // A view for a board of `columns` by `rows` centred on the screen. None if it doesn't fit.
+    pub fn fit_board(
+        columns: u16,
+        rows: u16,
+        theme: GlyphTheme,
+        screen: &DrawScreen,
+    ) -> Option<Self> {
+        let width = columns.checked_mul(theme.cell_width)?.checked_add(2)?;
+        let height = rows.checked_add(1)?;
+        // Leave room for the screen border below the board
+        if !screen_fits(screen)
+            || width.saturating_add(2 * SIDE_MARGIN) > screen.width()
+            || height.saturating_add(3) > screen.height()
+        {
+            return None;
+        }
+        Some(BoardView::new(
+            ((screen.width() - width) / 2, 1),
+            width,
+            height,
+            theme,
+        ))
+    }
+
+    // Number of GameBoard columns that fit inside the border
+    pub fn columns(&self) -> u16 {
+        (self.width - 2) / self.theme.cell_width
+    }
+
+    // Number of GameBoard rows that fit inside the border
+    pub fn rows(&self) -> u16 {
+        self.height - 1
+    }
+
+    // Draw the edge of the board. A board that wraps gets a dashed edge to show the snake can go
+    // through it.
+    pub fn draw_border(&self, wraps: bool, screen: &mut DrawScreen) {
+        let (corners, horizontal, vertical) = if wraps {
+            (['┌', '┐', '┘', '└'], '┄', '┆')
+        } else {
+            (['╔', '╗', '╝', '╚'], '═', '║')
+        };
+        // Draw corners of the draw screen
+        screen.update(
+            self.position.0,
+            self.position.1,
+            corners[0],
+            DrawColor::GameBorder,
+        );
+        screen.update(
+            self.position.0 + self.width - 1,
+            self.position.1,
+            corners[1],
+            DrawColor::GameBorder,
+        );
+        screen.update(
+            self.position.0 + self.width - 1,
+            self.position.1 + self.height,
+            corners[2],
+            DrawColor::GameBorder,
+        );
+        screen.update(
+            self.position.0,
+            self.position.1 + self.height,
+            corners[3],
+            DrawColor::GameBorder,
+        );
+        // Draw left and right borders.
+        for i in 1..self.height {
+            screen.update(
+                self.position.0,
+                self.position.1 + i,
+                vertical,
+                DrawColor::GameBorder,
+            );
+            screen.update(
+                self.position.0 + self.width - 1,
+                self.position.1 + i,
+                vertical,
+                DrawColor::GameBorder,
+            );
+        }
+        // Draw top and bottom border
+        for i in 1..self.width - 1 {
+            screen.update(
+                self.position.0 + i,
+                self.position.1,
+                horizontal,
+                DrawColor::GameBorder,
+            );
+            screen.update(
+                self.position.0 + i,
+                self.position.1 + self.height,
+                horizontal,
+                DrawColor::GameBorder,
+            );
+        }
+    }
+
+    pub fn clear_game_panel(&self, screen: &mut DrawScreen) {
+        for i in 1..self.height {
+            for j in 1..self.width - 1 {
+                screen.update(
+                    self.position.0 + j,
+                    self.position.1 + i,
+                    ' ',
+                    DrawColor::White,
+                );
+            }
+        }
+    }
+
+    // Draw a glyph in a GameBoard cell. Cells off the board, like where the head ends up after
+    // hitting the border, aren't drawn so they can't cover the border.
+    fn draw_element(
+        &self,
+        element: &SnakeElement,
+        glyph: &str,
+        color: DrawColor,
+        screen: &mut DrawScreen,
+    ) {
+        if element.x < 0
+            || element.y < 0
+            || element.x as u16 >= self.columns()
+            || element.y as u16 >= self.rows()
+        {
+            return;
+        }
+        screen.update_with_string(
+            self.position.0 + (element.x as u16 * self.theme.cell_width + 1),
+            self.position.1 + (element.y as u16 + 1),
+            glyph.to_string(),
+            color,
+        );
+    }
+
+    // Draw a piece of food on the game board
+    fn draw_food(&self, board: &GameBoard, screen: &mut DrawScreen) {
+        let (x, y) = board.food_position();
+        // There's no food once the board is full
+        if x < 0 || y < 0 {
+            return;
+        }
+        self.draw_element(
+            &SnakeElement { x, y },
+            self.theme.food,
+            DrawColor::Red,
+            screen,
+        );
+    }
+
+    // Draw the level's walls on the game board
+    fn draw_walls(&self, level: &Level, screen: &mut DrawScreen) {
+        for (x, y) in level.walls() {
+            self.draw_element(
+                &SnakeElement { x, y },
+                self.theme.wall,
+                DrawColor::GameBorder,
+                screen,
+            );
+        }
+    }
+
+    // Draw the exit, which looks like a wall until it opens
+    fn draw_exit(&self, (x, y): (i16, i16), open: bool, screen: &mut DrawScreen) {
+        let (glyph, color) = if open {
+            (self.theme.exit, DrawColor::Green)
+        } else {
+            (self.theme.wall, DrawColor::GameBorder)
+        };
+        self.draw_element(&SnakeElement { x, y }, glyph, color, screen);
+    }
+
+    // Draw a level without a game on it, with the snake where it starts. Used by the level editor.
+    pub fn draw_level(&self, level: &Level, screen: &mut DrawScreen) {
+        self.clear_game_panel(screen);
+        self.draw_border(false, screen);
+        self.draw_walls(level, screen);
+        if let Some(exit) = level.exit() {
+            self.draw_exit(exit, true, screen);
+        }
+        for (i, (x, y)) in level.snake_start().enumerate() {
+            let glyph = if i == 0 {
+                self.theme.head
+            } else {
+                self.theme.body
+            };
+            self.draw_element(&SnakeElement { x, y }, glyph, DrawColor::Green, screen);
+        }
+    }
+
+    // Draw the level editor's cursor over a cell
+    pub fn draw_cursor(&self, x: i16, y: i16, screen: &mut DrawScreen) {
+        let glyph = if self.theme.cell_width == 2 {
+            "[]"
+        } else {
+            "X"
+        };
+        self.draw_element(&SnakeElement { x, y }, glyph, DrawColor::Red, screen);
+    }
+
+    // Draw the snake on the game board
+    fn draw_snake(&self, board: &GameBoard, screen: &mut DrawScreen) {
+        for (i, element) in board.snake_elements().enumerate() {
+            let glyph = if i == 0 {
+                self.theme.head
+            } else {
+                self.theme.body
+            };
+            self.draw_element(element, glyph, DrawColor::Green, screen);
+        }
+    }
+
+    // Draw how much food is left to open the exit above the speed level, on levels that have one
+    fn draw_target(&self, board: &GameBoard, screen: &mut DrawScreen) {
+        // Left out rather than drawn over the screen's border when the board is very short
+        let row = (self.height / 2).checked_sub(2).filter(|row| *row > 0);
+        let (Some(target), Some(row)) = (board.level().target(), row) else {
+            return;
+        };
+        let text = if board.is_exit_open() && board.exit().is_some() {
+            "Exit open!".to_string()
+        } else {
+            format!("Food: {}/{}", board.score().min(u32::from(target)), target)
+        };
+        screen.update_with_string(2, row, text, DrawColor::Green);
+    }
+
+    // Draw the score with the speed level above it, to the left of the board. It's labelled Speed
+    // so it isn't mixed up with the level being played, and goes above so the game over message
+    // doesn't cover it.
+    pub fn draw_score(&self, score: u32, level: u32, screen: &mut DrawScreen) {
+        screen.update_with_string(
+            2,
+            (self.height / 2).saturating_sub(1),
+            format!("Speed: {}", level),
+            DrawColor::White,
+        );
+        screen.update_with_string(
+            2,
+            self.height / 2,
+            format!("Score: {}", score),
+            DrawColor::White,
+        );
+    }
+
+    // Draw text centred horizontally on the board, `row` rows below the middle of it
+    pub fn draw_centred_text(
+        &self,
+        row: u16,
+        text: String,
+        color: DrawColor,
+        screen: &mut DrawScreen,
+    ) {
+        let x = (self.position.0 + self.width / 2).saturating_sub(text_width(&text) / 2);
+        screen.update_with_string(x, self.position.1 + self.height / 2 + row, text, color);
+    }
+
+    // `home` is what Esc goes back to, like "title"
+    pub fn draw_game_over(&self, board: &GameBoard, home: &str, screen: &mut DrawScreen) {
+        if board.has_won() {
+            self.draw_centred_text(
+                0,
+                "You filled the whole board, you win! Press Enter to play again.".to_string(),
+                DrawColor::Green,
+                screen,
+            );
+        } else {
+            self.draw_centred_text(
+                0,
+                "Oh my goodness you did such a big lose! Press Enter to try again.".to_string(),
+                DrawColor::Red,
+                screen,
+            );
+        }
+        self.draw_centred_text(
+            1,
+            format!("Esc goes back to the {} and Q quits.", home),
+            DrawColor::White,
+            screen,
+        );
+        // Show the seed so the game can be replayed with --seed
+        self.draw_centred_text(
+            2,
+            format!("Seed: {}", board.seed()),
+            DrawColor::White,
+            screen,
+        );
+    }
+
+    // Draw the high-score table below the game over message, as much of it as fits on the screen.
+    // The entry at `highlight` is the game that was just played.
+    pub fn draw_high_scores(
+        &self,
+        scores: &HighScores,
+        highlight: Option<usize>,
+        screen: &mut DrawScreen,
+    ) {
+        let first_row = 4;
+        let lines = high_score_lines(scores);
+        let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+        let x = (self.position.0 + self.width / 2).saturating_sub(width / 2);
+        let y = self.position.1 + self.height / 2 + first_row;
+        // Stop above the screen border
+        let space = screen.height().saturating_sub(y + 1);
+        for (i, line) in lines.into_iter().enumerate().take(space as usize) {
+            let color = if highlight == Some(i) {
+                DrawColor::Green
+            } else {
+                DrawColor::White
+            };
+            screen.update_with_string(x, y + i as u16, line, color);
+        }
+    }
+
+    // Ask for a name to go with a new high score
+    pub fn draw_name_entry(&self, name: &str, screen: &mut DrawScreen) {
+        self.draw_centred_text(
+            4,
+            "New high score! Type your name and press Enter.".to_string(),
+            DrawColor::Green,
+            screen,
+        );
+        // Clear the line so deleted characters disappear
+        let blank = " ".repeat(MAX_NAME_LENGTH + 2);
+        self.draw_centred_text(6, blank, DrawColor::White, screen);
+        self.draw_centred_text(6, format!("{}_", name), DrawColor::White, screen);
+    }
+
+    // Redraw the whole board apart from the score. This only touches the DrawScreen's back buffer
+    // so it's cheap, and the DrawScreen works out which cells actually changed when it draws.
+    pub fn draw(&self, board: &GameBoard, screen: &mut DrawScreen) {
+        self.clear_game_panel(screen);
+        self.draw_border(board.wraps(), screen);
+        self.draw_walls(board.level(), screen);
+        if let Some(exit) = board.exit() {
+            self.draw_exit(exit, board.is_exit_open(), screen);
+        }
+        self.draw_target(board, screen);
+        self.draw_food(board, screen);
+        self.draw_snake(board, screen);
+    }
+}
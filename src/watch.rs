@@ -0,0 +1,119 @@
+// Copyright (c) 2022 DanWillans
+use crate::scene::{Context, Scene, Transition};
+use crate::view::draw_screen_too_small;
+use crate::{BoardView, Command, DrawColor, InputEvent, Replay, ReplayPlayer};
+use crossterm::event::KeyCode;
+
+// The fastest and slowest a replay can be watched at, relative to how fast it was played
+const MAX_REPLAY_SPEED: f64 = 16.0;
+const MIN_REPLAY_SPEED: f64 = 0.25;
+
+// Watches a replay. Space pauses, the arrow keys step and change speed, the number keys seek and Q
+// or Esc stop watching.
+pub struct WatchScene {
+    player: ReplayPlayer,
+    // How many times faster than it was played the replay is shown
+    speed: f64,
+    paused: bool,
+}
+
+impl WatchScene {
+    pub fn new(replay: Replay) -> Self {
+        WatchScene {
+            player: ReplayPlayer::new(replay),
+            speed: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl Scene for WatchScene {
+    fn handle_input(&mut self, input: &InputEvent, context: &mut Context) -> Transition {
+        let key = match input {
+            InputEvent::Key(key) => key,
+            InputEvent::FocusLost => {
+                self.paused = true;
+                return Transition::Stay;
+            }
+            InputEvent::Resize(..) => return Transition::Stay,
+        };
+        if context.config.keys.command_for_key(key) == Some(Command::Quit) {
+            return Transition::Quit;
+        }
+        let player = &mut self.player;
+        let length = player.length();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => return Transition::Quit,
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Right | KeyCode::Char('.') => {
+                self.paused = true;
+                player.step();
+            }
+            KeyCode::Left | KeyCode::Char(',') => {
+                self.paused = true;
+                player.seek(player.position().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.speed = (self.speed * 2.0).min(MAX_REPLAY_SPEED)
+            }
+            KeyCode::Down | KeyCode::Char('-') => {
+                self.speed = (self.speed / 2.0).max(MIN_REPLAY_SPEED)
+            }
+            KeyCode::Home => player.seek(0),
+            KeyCode::End => player.seek(length),
+            KeyCode::Char(digit @ '0'..='9') => {
+                let tenths = digit.to_digit(10).unwrap_or(0);
+                player.seek((length as u64 * tenths as u64 / 10) as u32);
+            }
+            _ => {}
+        }
+        Transition::Stay
+    }
+
+    // The replay is stepped at the speed it was played at, times `speed`
+    fn tick_rate(&self, _context: &Context) -> Option<f64> {
+        if self.paused || self.player.is_finished() {
+            return None;
+        }
+        Some(self.player.speed() * self.speed)
+    }
+
+    fn tick(&mut self, _context: &mut Context) -> Transition {
+        self.player.step();
+        Transition::Stay
+    }
+
+    // Draw the replay's board with a status line along the bottom of the screen
+    fn draw(&self, context: &mut Context) {
+        let board = self.player.board();
+        let theme = context.config.theme;
+        let screen = &mut context.screen;
+        let Some(board_view) = BoardView::fit_board(board.columns(), board.rows(), theme, screen)
+        else {
+            draw_screen_too_small(
+                BoardView::screen_size_for(board.columns(), board.rows(), theme),
+                screen,
+            );
+            return;
+        };
+        board_view.draw(board, screen);
+        let level = self.player.replay().speed_curve.level(board.score());
+        board_view.draw_score(self.player.points(), level, screen);
+        if self.player.is_finished() {
+            board_view.draw_centred_text(0, "End of replay".to_string(), DrawColor::White, screen);
+        }
+        let status = format!(
+            "Step {}/{} x{}{}  Space pause  Arrows step/speed  0-9 seek  Q quit",
+            self.player.position(),
+            self.player.length(),
+            self.speed,
+            if self.paused { " paused" } else { "" }
+        );
+        screen.update_with_string(
+            2,
+            screen.height().saturating_sub(2),
+            status,
+            DrawColor::White,
+        );
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) 2022 DanWillans
+// Plays the recorded games in tests/replays and checks they still end the same way. A change to
+// the rules that makes one fail changes how saved games play, which needs REPLAY_VERSION bumped.
+use rusty_snake::Replay;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn fixtures() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/replays");
+    let mut paths: Vec<PathBuf> = fs::read_dir(directory)
+        .expect("tests/replays is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn recorded_games_play_the_same() {
+    let paths = fixtures();
+    assert!(!paths.is_empty());
+    for path in paths {
+        let replay = Replay::load(&path).unwrap();
+        let board = replay
+            .verify()
+            .unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+        assert_eq!(Some(board.score()), replay.score, "{}", path.display());
+        assert_eq!(Some(board.steps()), replay.steps, "{}", path.display());
+    }
+}
+
+#[test]
+fn verify_fails_when_the_game_plays_differently() {
+    for path in fixtures() {
+        let mut replay = Replay::load(&path).unwrap();
+        replay.score = replay.score.map(|score| score + 1);
+        assert!(replay.verify().is_err(), "{}", path.display());
+    }
+}
+
+#[test]
+fn saved_replays_load_the_same() {
+    let directory =
+        std::env::temp_dir().join(format!("rusty_snake_replays_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    for path in fixtures() {
+        let replay = Replay::load(&path).unwrap();
+        let copy = directory.join(path.file_name().unwrap());
+        replay.save(&copy).unwrap();
+        assert_eq!(Replay::load(&copy).unwrap(), replay, "{}", path.display());
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn seeds_too_big_for_a_toml_integer_are_kept() {
+    let path = std::env::temp_dir().join(format!("rusty_snake_seed_{}.toml", std::process::id()));
+    let mut replay = Replay::load(&fixtures()[0]).unwrap();
+    replay.seed = u64::MAX - 1;
+    replay.save(&path).unwrap();
+    assert_eq!(Replay::load(&path).unwrap().seed, u64::MAX - 1);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn replays_saved_together_get_their_own_files() {
+    let directory =
+        std::env::temp_dir().join(format!("rusty_snake_replay_dir_{}", std::process::id()));
+    let replay = Replay::load(&fixtures()[0]).unwrap();
+    let paths: Vec<PathBuf> = (0..3)
+        .map(|_| replay.save_to_directory(&directory).unwrap())
+        .collect();
+    assert_ne!(paths[0], paths[1]);
+    assert_ne!(paths[1], paths[2]);
+    assert_ne!(paths[0], paths[2]);
+    for path in &paths {
+        assert_eq!(Replay::load(path).unwrap(), replay, "{}", path.display());
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}